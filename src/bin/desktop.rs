@@ -12,9 +12,12 @@ fn App() -> Element {
     let mut spline_step = use_signal(|| 20u32);
     let mut verbose = use_signal(|| false);
     let mut info_flag = use_signal(|| false);
+    let mut text_mode = use_signal(dxf2elmt::qelmt::TextMode::default);
     let mut is_processing = use_signal(|| false);
     let mut status = use_signal(|| String::new());
     let mut preview_stats = use_signal(|| Option::<dxf2elmt::ConversionStats>::None);
+    let mut report = use_signal(|| Option::<dxf2elmt::qelmt::Report>::None);
+    let mut preview = use_signal(|| Option::<String>::None);
 
     rsx! {
         div {
@@ -35,13 +38,18 @@ fn App() -> Element {
                             selected_path.set(Some(path.display().to_string()));
                             status.set(String::new());
                             preview_stats.set(None);
+                            report.set(None);
+                            preview.set(None);
                             // Cargar y mostrar resumen de entidades (previo a convertir)
                             let path_for_preview = path.clone();
                             dioxus::core::spawn(async move {
                                 use dxf::Drawing;
                                 use dxf::entities::EntityType;
                                 let res = std::thread::spawn(move || {
-                                    let drawing = Drawing::load_file(&path_for_preview).map_err(|e| e.to_string())?;
+                                    let bytes = std::fs::read(&path_for_preview).map_err(|e| e.to_string())?;
+                                    let drawing = Drawing::load(&mut bytes.as_slice()).map_err(|e| e.to_string())?;
+                                    // El crate dxf no lee los HATCH, se cuentan aparte
+                                    let hatches = u32::try_from(dxf2elmt::qelmt::Hatches::parse(&bytes).len()).unwrap_or(u32::MAX);
                                     let mut circles = 0u32;
                                     let mut lines = 0u32;
                                     let mut arcs = 0u32;
@@ -68,7 +76,7 @@ fn App() -> Element {
                                     });
                                     Ok::<_, String>(dxf2elmt::ConversionStats {
                                         circles, lines, arcs, splines, texts, ellipses,
-                                        polylines, lwpolylines, solids, blocks, unsupported,
+                                        polylines, lwpolylines, solids, hatches, blocks, unsupported,
                                         elapsed_ms: 0,
                                     })
                                 }).join();
@@ -99,6 +107,7 @@ fn App() -> Element {
                             li { "Polylines: {st.polylines}" }
                             li { "LwPolylines: {st.lwpolylines}" }
                             li { "Solids: {st.solids}" }
+                            li { "Hatches: {st.hatches}" }
                             li { "Blocks: {st.blocks}" }
                             li { "Unsupported: {st.unsupported}" }
                         }
@@ -134,6 +143,15 @@ fn App() -> Element {
                         }
                         span { " info (estadísticas)" }
                     }
+                    label { "Textos:" }
+                    select {
+                        oninput: move |e| {
+                            if let Ok(mode) = e.value().parse() { text_mode.set(mode); }
+                        },
+                        style: "padding: 6px; border: 1px solid #d1d5db; border-radius: 4px;",
+                        option { value: "dynamic", selected: text_mode() == dxf2elmt::qelmt::TextMode::Dynamic, "dinámicos" }
+                        option { value: "static", selected: text_mode() == dxf2elmt::qelmt::TextMode::Static, "estáticos" }
+                    }
                 }
 
                 button {
@@ -148,13 +166,14 @@ fn App() -> Element {
                             let v = verbose();
                             let i = info_flag();
                             let step = spline_step();
+                            let tm = text_mode();
                             dioxus::core::spawn(async move {
-                                use dxf2elmt::{convert_dxf_file, ConversionOptions};
+                                use dxf2elmt::{convert_dxf_file, data_url, ConversionOptions};
                                 use std::path::PathBuf;
                                 use std::path::Path;
                                 let result = std::thread::spawn(move || {
                                     let pb = PathBuf::from(path_owned);
-                                    let opts = ConversionOptions { spline_step: step, verbose: v, info: i };
+                                    let opts = ConversionOptions { spline_step: step, verbose: v, info: i, text_mode: tm, preview: true, ..Default::default() };
                                     convert_dxf_file(&pb, &opts)
                                 }).join();
                                 match result {
@@ -165,7 +184,14 @@ fn App() -> Element {
                                                 last_output_dir.set(Some(parent.display().to_string()));
                                             }
                                         }
-                                        status.set(format!("OK: {}", conv.message));
+                                        if conv.warnings.is_empty() {
+                                            status.set(format!("OK: {}", conv.message));
+                                        } else {
+                                            status.set(format!("OK: {} ({} entidades no convertidas)", conv.message, conv.warnings.len()));
+                                        }
+                                        report.set(Some(conv.report));
+                                        // La vista previa se muestra como una data url, así no hace falta escribirla a disco
+                                        preview.set(conv.svg_preview.as_deref().map(|svg| data_url("image/svg+xml", svg)));
                                     }
                                     Ok(Err(e)) => status.set(format!("Error: {e}")),
                                     Err(_) => status.set("Error: fallo interno al convertir".to_string()),
@@ -179,6 +205,27 @@ fn App() -> Element {
                 if !status().is_empty() {
                     div { style: "color: #111827;", "{status()}" }
                 }
+                if let Some(src) = preview() {
+                    img {
+                        src: "{src}",
+                        alt: "Vista previa del elemento",
+                        style: "display: block; width: 100%; max-height: 400px; object-fit: contain; background: white; border: 1px solid #e5e7eb; border-radius: 8px;",
+                    }
+                }
+                if let Some(rep) = report() {
+                    {
+                        let sum = rep.summary();
+                        rsx! {
+                            details {
+                                style: "background: #f3f4f6; border: 1px solid #e5e7eb; border-radius: 8px; padding: 12px;",
+                                summary {
+                                    "Informe: {sum.converted} convertidas, {sum.approximated} aproximadas, {sum.expanded} bloques expandidos, {sum.dropped} descartadas"
+                                }
+                                pre { style: "white-space: pre-wrap; font-size: 12px; margin: 8px 0 0 0;", "{rep}" }
+                            }
+                        }
+                    }
+                }
                 button {
                     disabled: last_output_dir().is_none(),
                     style: "background: #374151; color: white; border: none; padding: 10px 16px; border-radius: 6px; cursor: pointer; width: fit-content;",
@@ -204,5 +251,3 @@ fn App() -> Element {
         }
     }
 }
-
-
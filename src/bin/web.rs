@@ -1,4 +1,5 @@
 use dioxus::prelude::*;
+use dxf2elmt::{convert_dxf_bytes, data_url, ConversionOptions, ConversionResult};
 
 fn main() {
     launch(App);
@@ -6,10 +7,13 @@ fn main() {
 
 #[component]
 fn App() -> Element {
-    let mut file_name = use_signal(|| String::new());
+    let mut files = use_signal(Vec::<DxfFile>::new);
+    let mut results = use_signal(Vec::<WebConversion>::new);
+    let mut is_dragging = use_signal(|| false);
     let mut is_processing = use_signal(|| false);
     let mut error_message = use_signal(|| String::new());
     let mut spline_step = use_signal(|| 20u32);
+    let mut text_mode = use_signal(dxf2elmt::qelmt::TextMode::default);
 
     rsx! {
         div {
@@ -21,35 +25,63 @@ fn App() -> Element {
             }
             p {
                 style: "color: #6b7280; margin-bottom: 30px;",
-                "Convert DXF files to QElectroTech ELMT format"
+                "Convert DXF files to QElectroTech ELMT format. The files are converted in the browser and never leave your computer."
             }
 
             div {
-                style: "background: #f9fafb; border: 2px dashed #d1d5db; border-radius: 8px; padding: 30px; text-align: center; margin-bottom: 20px;",
+                style: if is_dragging() {
+                    "background: #eff6ff; border: 2px dashed #2563eb; border-radius: 8px; padding: 30px; text-align: center; margin-bottom: 20px;"
+                } else {
+                    "background: #f9fafb; border: 2px dashed #d1d5db; border-radius: 8px; padding: 30px; text-align: center; margin-bottom: 20px;"
+                },
+                ondragover: move |evt| {
+                    // without this the browser opens the file instead of dropping it here
+                    evt.prevent_default();
+                    is_dragging.set(true);
+                },
+                ondragleave: move |_| is_dragging.set(false),
+                ondrop: move |evt| {
+                    evt.prevent_default();
+                    is_dragging.set(false);
+                    let dropped = evt.data_transfer().files();
+                    spawn(async move {
+                        match read_files(dropped).await {
+                            Ok(read) => files.write().extend(read),
+                            Err(e) => error_message.set(e),
+                        }
+                    });
+                },
                 label {
                     style: "display: block; margin-bottom: 10px; font-weight: 500; color: #374151;",
-                    "Select DXF File:"
+                    "Select or drop DXF files:"
                 }
                 input {
                     r#type: "file",
                     accept: ".dxf",
+                    multiple: true,
                     onchange: move |evt| {
-                        // File handling will be implemented with proper WASM file API
-                        let value = evt.value();
-                        if !value.is_empty() {
-                            // Extract filename from path
-                            let path = value.replace('\\', "/");
-                            if let Some(name) = path.split('/').last() {
-                                file_name.set(name.to_string());
+                        let selected = evt.files();
+                        spawn(async move {
+                            match read_files(selected).await {
+                                Ok(read) => files.write().extend(read),
+                                Err(e) => error_message.set(e),
                             }
-                        }
+                        });
                     },
                     style: "margin: 10px 0; padding: 8px; border: 1px solid #d1d5db; border-radius: 4px; width: 100%; max-width: 400px;"
                 }
-                if !file_name().is_empty() {
+                for file in files() {
                     p {
+                        key: "{file.name}",
                         style: "margin-top: 10px; color: #059669; font-weight: 500;",
-                        "Selected: {file_name()}"
+                        "Selected: {file.name}"
+                    }
+                }
+                if !files().is_empty() {
+                    button {
+                        onclick: move |_| files.write().clear(),
+                        style: "margin-top: 10px; background: none; border: 1px solid #d1d5db; padding: 4px 12px; border-radius: 4px; cursor: pointer;",
+                        "Clear"
                     }
                 }
             }
@@ -78,25 +110,53 @@ fn App() -> Element {
                 }
             }
 
+            div {
+                style: "margin-bottom: 20px;",
+                label {
+                    style: "display: block; margin-bottom: 5px; font-weight: 500; color: #374151;",
+                    "Text:"
+                }
+                select {
+                    oninput: move |evt| {
+                        if let Ok(mode) = evt.value().parse() {
+                            text_mode.set(mode);
+                        }
+                    },
+                    style: "padding: 8px; border: 1px solid #d1d5db; border-radius: 4px;",
+                    option { value: "dynamic", selected: text_mode() == dxf2elmt::qelmt::TextMode::Dynamic, "Dynamic text" }
+                    option { value: "static", selected: text_mode() == dxf2elmt::qelmt::TextMode::Static, "Static text" }
+                }
+            }
+
             button {
                 onclick: move |_| {
-                    if file_name().is_empty() {
+                    if files().is_empty() {
                         error_message.set("Please select a file first".to_string());
                         return;
                     }
                     is_processing.set(true);
                     error_message.set(String::new());
 
-                    // Note: File conversion in browser requires:
-                    // 1. Reading file content via FileReader API (WASM)
-                    // 2. Processing the DXF file in memory
-                    // 3. Generating and downloading the ELMT file
-                    // This is a placeholder - full implementation would require
-                    // additional WASM bindings for file I/O
-                    error_message.set("Web conversion is in development. For now, please use the CLI version: dxf2elmt <file.dxf>".to_string());
+                    let options = ConversionOptions {
+                        spline_step: spline_step(),
+                        text_mode: text_mode(),
+                        preview: true,
+                        ..Default::default()
+                    };
+                    let mut converted = Vec::new();
+                    for file in files().iter() {
+                        match convert_dxf_bytes(&file.bytes, element_name(&file.name), &options) {
+                            Ok(result) => converted.push(WebConversion {
+                                file_name: file.name.clone(),
+                                result,
+                            }),
+                            Err(e) => error_message.set(format!("{}: {e:#}", file.name)),
+                        }
+                    }
+                    results.set(converted);
                     is_processing.set(false);
                 },
-                disabled: is_processing() || file_name().is_empty(),
+                disabled: is_processing() || files().is_empty(),
                 style: "background: #2563eb; color: white; border: none; padding: 12px 24px; border-radius: 6px; font-size: 16px; font-weight: 500; cursor: pointer; width: 100%; max-width: 400px;",
                 if is_processing() {
                     "Processing..."
@@ -112,20 +172,120 @@ fn App() -> Element {
                 }
             }
 
-            div {
-                style: "margin-top: 30px; padding: 20px; background: #f0f9ff; border: 1px solid #93c5fd; border-radius: 8px;",
-                h3 {
-                    style: "color: #1e40af; margin-bottom: 10px;",
-                    "ℹ️ Web Interface"
+            for conv in results() {
+                ConversionCard { key: "{conv.file_name}", conv }
+            }
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+struct DxfFile {
+    name: String,
+    bytes: Vec<u8>,
+}
+
+#[derive(Clone)]
+struct WebConversion {
+    file_name: String,
+    result: ConversionResult,
+}
+
+// ConversionResult doesn't implement PartialEq, the props only need to tell if it's a different conversion
+impl PartialEq for WebConversion {
+    fn eq(&self, other: &Self) -> bool {
+        self.file_name == other.file_name && self.result.xml_content == other.result.xml_content
+    }
+}
+
+async fn read_files(files: Vec<dioxus::html::FileData>) -> Result<Vec<DxfFile>, String> {
+    let mut read = Vec::with_capacity(files.len());
+    for file in files {
+        let name = file.name();
+        let bytes = file
+            .read_bytes()
+            .await
+            .map_err(|e| format!("Could not read {name}: {e}"))?;
+        read.push(DxfFile {
+            name,
+            bytes: bytes.to_vec(),
+        });
+    }
+    Ok(read)
+}
+
+// the element takes the name of the file, without the .dxf
+fn element_name(file_name: &str) -> &str {
+    std::path::Path::new(file_name)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or(file_name)
+}
+
+#[component]
+fn ConversionCard(conv: WebConversion) -> Element {
+    let result = &conv.result;
+    let download_name = format!("{}.elmt", element_name(&conv.file_name));
+    let href = data_url("application/xml", &result.xml_content);
+    let preview = result
+        .svg_preview
+        .as_deref()
+        .map(|svg| data_url("image/svg+xml", svg));
+
+    rsx! {
+        div {
+            style: "margin-top: 20px; padding: 20px; background: #f0fdf4; border: 1px solid #86efac; border-radius: 8px;",
+            h3 {
+                style: "color: #166534; margin-bottom: 10px;",
+                "{result.message}"
+            }
+            if !result.warnings.is_empty() {
+                details {
+                    style: "margin-bottom: 10px; color: #92400e;",
+                    summary { "{result.warnings.len()} entities could not be converted" }
+                    ul {
+                        for warning in result.warnings.iter() {
+                            li { "{warning}" }
+                        }
+                    }
                 }
-                p {
-                    style: "color: #1e40af;",
-                    "The web interface is currently in development. "
-                    "For now, please use the CLI version:"
+            }
+            if let Some(stats) = &result.stats {
+                div {
+                    style: "display: grid; grid-template-columns: repeat(auto-fill, minmax(120px, 1fr)); gap: 8px; margin-bottom: 16px;",
+                    StatItem { label: "Lines", value: stats.lines }
+                    StatItem { label: "Circles", value: stats.circles }
+                    StatItem { label: "Arcs", value: stats.arcs }
+                    StatItem { label: "Ellipses", value: stats.ellipses }
+                    StatItem { label: "Splines", value: stats.splines }
+                    StatItem { label: "Texts", value: stats.texts }
+                    StatItem { label: "Polylines", value: stats.polylines }
+                    StatItem { label: "LwPolylines", value: stats.lwpolylines }
+                    StatItem { label: "Solids", value: stats.solids }
+                    StatItem { label: "Hatches", value: stats.hatches }
+                    StatItem { label: "Blocks", value: stats.blocks }
+                    StatItem { label: "Unsupported", value: stats.unsupported }
+                }
+            }
+            if let Some(preview) = preview {
+                img {
+                    src: "{preview}",
+                    alt: "Preview of {download_name}",
+                    style: "display: block; width: 100%; max-height: 400px; object-fit: contain; margin-bottom: 16px; background: white; border-radius: 4px;",
                 }
-                code {
-                    style: "display: block; margin-top: 10px; padding: 10px; background: white; border-radius: 4px; font-family: monospace;",
-                    "dxf2elmt <file.dxf> [options]"
+            }
+            a {
+                href: "{href}",
+                download: "{download_name}",
+                style: "display: inline-block; background: #16a34a; color: white; padding: 10px 16px; border-radius: 6px; text-decoration: none; font-weight: 500;",
+                "Download {download_name}"
+            }
+            details {
+                style: "margin-top: 16px;",
+                summary { style: "cursor: pointer; color: #374151;", "XML preview" }
+                pre {
+                    style: "max-height: 400px; overflow: auto; padding: 10px; background: white; border-radius: 4px; font-size: 12px;",
+                    "{result.xml_content}"
                 }
             }
         }
@@ -148,4 +308,3 @@ fn StatItem(label: String, value: u32) -> Element {
         }
     }
 }
-
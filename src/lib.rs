@@ -9,9 +9,15 @@ pub mod file_writer;
 use anyhow::{Context, Result};
 use dxf::entities::EntityType;
 use dxf::Drawing;
-use qelmt::Definition;
+use qelmt::style::{LineStyle, LineWeightBreakpoints};
+use qelmt::{Definition, EntityError, Hatches, LinkType, Report, TerminalRules, TextMode};
 use simple_xml_builder::XMLElement;
-use std::path::Path;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs::{self, File};
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
 use std::time::Instant;
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -25,6 +31,7 @@ pub struct ConversionStats {
     pub polylines: u32,
     pub lwpolylines: u32,
     pub solids: u32,
+    pub hatches: u32,
     pub blocks: u32,
     pub unsupported: u32,
     pub elapsed_ms: u128,
@@ -35,42 +42,258 @@ pub struct ConversionResult {
     pub success: bool,
     pub message: String,
     pub stats: Option<ConversionStats>,
-    pub xml_content: Option<String>,
+
+    /// The .elmt file contents
+    pub xml_content: String,
+
+    /// The element drawn as SVG, when `options.preview` is set
+    pub svg_preview: Option<String>,
+
+    /// The element drawn into a PNG, when `options.thumbnail_dpi` is set
+    #[serde(skip)]
+    pub thumbnail: Option<Vec<u8>>,
+
+    /// The entities that couldn't be converted and were left out of the element
+    pub warnings: Vec<EntityError>,
+
+    /// What happened to each of the entities, including the ones inside of blocks
+    pub report: Report,
+
+    /// Where [`convert_dxf_file`] wrote the .elmt, `None` when it didn't write one
+    #[serde(skip)]
+    pub elmt_path: Option<PathBuf>,
 }
 
+#[derive(Clone)]
 pub struct ConversionOptions {
     pub spline_step: u32,
+
+    /// How far (in drawing units) the polygon a rotated ellipse gets turned into can stray from the ellipse
+    pub ellipse_tolerance: f64,
+
+    /// Only print the element instead of writing the .elmt, for [`convert_dxf_file`]
     pub verbose: bool,
     pub info: bool,
+
+    /// Line type name -> QET line style, for custom line types that can't be
+    /// worked out from the LTYPE table. Names are matched case insensitively
+    pub line_types: HashMap<String, LineStyle>,
+
+    /// Where the DXF lineweights (in mm) get split up into the QET line weights
+    pub line_weights: LineWeightBreakpoints,
+
+    /// Draw everything in black instead of using the colors from the drawing
+    pub monochrome: bool,
+
+    /// Whether TEXT and MTEXT entities become static or dynamic text
+    pub text_mode: TextMode,
+
+    /// Layer name -> text mode, for the layers that should go against `text_mode`.
+    /// Names are matched case insensitively
+    pub text_layers: HashMap<String, TextMode>,
+
+    /// Measure texts with the font when working out the size of the element,
+    /// instead of guessing from the number of characters
+    pub font_metrics: bool,
+
+    /// Which entities in the drawing mark the terminals of the element, None to not look for any
+    pub terminals: Option<TerminalRules>,
+
+    /// DXF attribute tag or drawing property -> QET element information (manufacturer,
+    /// `manufacturer_reference`, ...), names are matched case insensitively. An empty
+    /// element information leaves that attribute or property out
+    pub element_info: HashMap<String, String>,
+
+    /// The link type of the element (master, slave...), None to take it from a
+    /// `LINK_TYPE` attribute in the drawing, or leave it simple when there isn't one
+    pub link_type: Option<LinkType>,
+
+    /// Kind information name -> value (type, state, number...) for the master, slave
+    /// and terminal elements, over what the KIND_* attributes in the drawing say
+    pub kind_infos: HashMap<String, String>,
+
+    /// Language (en, fr, de...) -> name of the element, over the `NAME_*` attributes in the
+    /// drawing. Without an English one the element is named after the file in English
+    pub names: HashMap<String, String>,
+
+    /// Derive the uuids of the element, its texts and terminals from the name and the
+    /// contents of the .dxf instead of making up random ones, so converting the same
+    /// file again gives back the exact same .elmt. The texts are measured without
+    /// `font_metrics` then, so the installed fonts don't change the result
+    pub deterministic: bool,
+
+    /// Also draw the element as SVG, in [`ConversionResult::svg_preview`]
+    pub preview: bool,
+
+    /// Also draw the element into a PNG at this DPI, in [`ConversionResult::thumbnail`]
+    pub thumbnail_dpi: Option<f64>,
 }
 
 impl Default for ConversionOptions {
     fn default() -> Self {
         Self {
             spline_step: 20,
+            ellipse_tolerance: qelmt::DEFAULT_ELLIPSE_TOLERANCE,
             verbose: false,
             info: false,
+            line_types: HashMap::new(),
+            line_weights: LineWeightBreakpoints::default(),
+            monochrome: false,
+            text_mode: TextMode::default(),
+            text_layers: HashMap::new(),
+            font_metrics: true,
+            terminals: None,
+            element_info: qelmt::info::default_info_map(),
+            link_type: None,
+            kind_infos: HashMap::new(),
+            names: HashMap::new(),
+            deterministic: false,
+            preview: false,
+            thumbnail_dpi: None,
         }
     }
 }
 
+/// Converts the .dxf file and, unless `options.verbose` is set, writes the
+/// .elmt next to it (see [`file_writer::write_elmt`])
+///
+/// # Errors
+///
+/// If the .dxf or the .names file next to it can't be read or parsed, or the
+/// .elmt can't be written
 pub fn convert_dxf_file(
     file_path: &Path,
     options: &ConversionOptions,
 ) -> Result<ConversionResult> {
-    let now = Instant::now();
     let friendly_file_name = file_path
         .file_stem()
         .unwrap_or_else(|| file_path.as_os_str())
         .to_string_lossy()
         .to_string();
 
-    // Load DXF file
-    let drawing = Drawing::load_file(file_path).context(format!(
-        "Failed to load {friendly_file_name}...\n\tMake sure the file is a valid .dxf file.",
-    ))?;
+    let options = match read_names_file(file_path)? {
+        Some(names) => Cow::Owned(ConversionOptions {
+            names: names.into_iter().chain(options.names.clone()).collect(),
+            ..options.clone()
+        }),
+        None => Cow::Borrowed(options),
+    };
+
+    let file = File::open(file_path).with_context(|| load_err(&friendly_file_name))?;
+    let mut result = convert_dxf_reader(BufReader::new(file), &friendly_file_name, &options)?;
 
-    let q_elmt = Definition::new(friendly_file_name.clone(), options.spline_step, &drawing);
+    // Create output file if not verbose
+    if !options.verbose {
+        result.elmt_path = Some(file_writer::write_elmt(file_path, &result.xml_content)?);
+    }
+
+    Ok(result)
+}
+
+/// Converts a .dxf (ascii or binary) read from anywhere, without touching the disk.
+/// The element is called `name`
+///
+/// # Errors
+///
+/// If reading fails or what's read isn't a .dxf
+pub fn convert_dxf_reader(
+    mut reader: impl Read,
+    name: &str,
+    options: &ConversionOptions,
+) -> Result<ConversionResult> {
+    let mut bytes = Vec::new();
+    reader
+        .read_to_end(&mut bytes)
+        .with_context(|| load_err(name))?;
+    convert_dxf_bytes(&bytes, name, options)
+}
+
+/// Same as [`convert_dxf_reader`], for a .dxf that's already in memory
+///
+/// # Errors
+///
+/// If the bytes aren't a .dxf
+pub fn convert_dxf_bytes(
+    bytes: &[u8],
+    name: &str,
+    options: &ConversionOptions,
+) -> Result<ConversionResult> {
+    let start = start_timer();
+
+    // Load DXF file, the hatches are read from the raw contents since the dxf crate skips them
+    let drawing = Drawing::load(&mut &bytes[..]).with_context(|| load_err(name))?;
+    let hatches = Hatches::parse(bytes);
+
+    Ok(convert(
+        &drawing,
+        Some(bytes),
+        &hatches,
+        name,
+        options,
+        start,
+    ))
+}
+
+/// Converts a drawing that's already loaded. The dxf crate doesn't read hatches,
+/// so they're passed in separately ([`Hatches::parse`] on the raw file, or
+/// `Hatches::default()` to leave them out)
+#[must_use]
+pub fn convert_drawing(
+    drawing: &Drawing,
+    hatches: &Hatches,
+    name: &str,
+    options: &ConversionOptions,
+) -> ConversionResult {
+    convert(drawing, None, hatches, name, options, start_timer())
+}
+
+/// Percent-encodes `content` into a `data:` url, which is how the apps hand the
+/// .elmt and the preview to the browser or webview without writing them to disk
+#[must_use]
+pub fn data_url(mime: &str, content: &str) -> String {
+    let mut url = format!("data:{mime};charset=utf-8,");
+    for byte in content.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-_.~".contains(&byte) {
+            url.push(char::from(byte));
+        } else {
+            let _ = write!(url, "%{byte:02X}");
+        }
+    }
+    url
+}
+
+//the names of the element in the .names file next to the .dxf, if there's one
+fn read_names_file(dxf_path: &Path) -> Result<Option<Vec<(String, String)>>> {
+    let names_path = dxf_path.with_extension(qelmt::names::NAMES_FILE_EXTENSION);
+    if !names_path.is_file() {
+        return Ok(None);
+    }
+
+    let contents = fs::read_to_string(&names_path)
+        .with_context(|| format!("Failed to read {}", names_path.display()))?;
+    qelmt::names::parse_names_file(&contents)
+        .map(Some)
+        .map_err(|err| anyhow::anyhow!("{}, {err}", names_path.display()))
+}
+
+fn load_err(name: &str) -> String {
+    format!("Failed to load {name}...\n\tMake sure the file is a valid .dxf file.")
+}
+
+//Instant isn't available in the browser (it panics), so there's no timing on wasm
+fn start_timer() -> Option<Instant> {
+    (!cfg!(target_arch = "wasm32")).then(Instant::now)
+}
+
+fn convert(
+    drawing: &Drawing,
+    contents: Option<&[u8]>,
+    hatches: &Hatches,
+    name: &str,
+    options: &ConversionOptions,
+    start: Option<Instant>,
+) -> ConversionResult {
+    let q_elmt = Definition::with_contents(name.to_string(), drawing, contents, hatches, options);
 
     // Initialize counts
     let mut circle_count: u32 = 0;
@@ -101,22 +324,11 @@ pub fn convert_dxf_file(
     });
 
     // Generate XML
-    let out_xml = XMLElement::from(&q_elmt);
-    let xml_content = if options.verbose {
-        Some(format!("{}", out_xml))
-    } else {
-        None
-    };
+    let xml_content = XMLElement::from(&q_elmt).to_string();
+    let svg_preview = options.preview.then(|| q_elmt.svg().to_string());
+    let thumbnail = options.thumbnail_dpi.and_then(|dpi| q_elmt.thumbnail(dpi));
 
-    // Create output file if not verbose
-    if !options.verbose {
-        let out_file = file_writer::create_file(false, options.info, file_path)?;
-        out_xml
-            .write(&out_file)
-            .context("Failed to write output file.")?;
-    }
-
-    let elapsed_ms = now.elapsed().as_millis();
+    let elapsed_ms = start.map_or(0, |start| start.elapsed().as_millis());
 
     let stats = ConversionStats {
         circles: circle_count,
@@ -128,16 +340,21 @@ pub fn convert_dxf_file(
         polylines: polyline_count,
         lwpolylines: lwpolyline_count,
         solids: solid_count,
+        hatches: u32::try_from(hatches.len()).unwrap_or(u32::MAX),
         blocks: block_count,
         unsupported: other_count,
         elapsed_ms,
     };
 
-    Ok(ConversionResult {
+    ConversionResult {
         success: true,
-        message: format!("Successfully converted {}", friendly_file_name),
+        message: format!("Successfully converted {name}"),
         stats: Some(stats),
         xml_content,
-    })
+        svg_preview,
+        thumbnail,
+        warnings: q_elmt.warnings(),
+        report: q_elmt.report().clone(),
+        elmt_path: None,
+    }
 }
-
@@ -6,10 +6,16 @@
 )]
 //#![deny(clippy::unwrap_used, clippy::expect_used, clippy::panic)]
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::Parser;
+use dxf2elmt::qelmt::info::{default_info_map, ELEMENT_INFO_KEYS};
+use dxf2elmt::qelmt::names::parse_name;
+use dxf2elmt::qelmt::report::{EntityReport, Summary};
+use dxf2elmt::qelmt::style::{LineStyle, LineWeightBreakpoints};
+use dxf2elmt::qelmt::{LinkType, Report, TerminalRules, TextMode};
 use dxf2elmt::{convert_dxf_file, ConversionOptions};
-use std::{io, path::PathBuf};
+use std::io;
+use std::path::{Path, PathBuf};
 use tracing::{span, trace, Level};
 use tracing_subscriber::prelude::*;
 
@@ -28,19 +34,219 @@ struct Args {
     #[clap(short, long, value_parser, default_value_t = false)]
     verbose: bool,
 
-    /// Converts text entities into dynamic text instead of the default text box
-    #[clap(short, long, value_parser, default_value_t = false)]
+    /// Converts text entities into dynamic text, same as --text-mode dynamic
+    #[clap(
+        short,
+        long,
+        value_parser,
+        default_value_t = false,
+        conflicts_with = "text_mode"
+    )]
     dtext: bool,
 
+    /// What text entities get converted into, static or dynamic text
+    #[clap(long, value_name = "MODE", default_value = "dynamic")]
+    text_mode: TextMode,
+
+    /// Overrides the text mode for the text on one layer, e.g. --text-layer LABELS=static. Can be repeated
+    #[clap(long = "text-layer", value_name = "LAYER=MODE", value_parser = parse_text_layer)]
+    text_layers: Vec<(String, TextMode)>,
+
+    /// Guess the size of texts from the number of characters instead of measuring them with the font
+    #[clap(long)]
+    no_font_metrics: bool,
+
+    /// Turns the points, small circles, blocks and attribute definitions following the terminal conventions below into terminals
+    #[clap(long)]
+    terminals: bool,
+
+    /// Points and small circles on this layer become terminals. Can be repeated
+    #[clap(
+        long = "terminal-layer",
+        value_name = "LAYER",
+        default_value = "TERMINALS",
+        requires = "terminals"
+    )]
+    terminal_layers: Vec<String>,
+
+    /// Inserts of the blocks matching this name become terminals, * matches anything. Can be repeated
+    #[clap(
+        long = "terminal-block",
+        value_name = "PATTERN",
+        default_value = "TERMINAL*",
+        requires = "terminals"
+    )]
+    terminal_blocks: Vec<String>,
+
+    /// Attribute definitions with this tag become terminals, named after their default value. Can be repeated
+    #[clap(
+        long = "terminal-tag",
+        value_name = "TAG",
+        default_value = "TERM",
+        requires = "terminals"
+    )]
+    terminal_tags: Vec<String>,
+
+    /// The biggest radius (in mm) of a circle on a terminal layer that still counts as a terminal
+    #[clap(long, value_parser, default_value_t = 2.0, requires = "terminals")]
+    terminal_radius: f64,
+
+    /// Fills an element information (manufacturer, `manufacturer_reference`, designation...) from an attribute tag or drawing property, e.g. --element-info `ARTNR=manufacturer_reference`. Leave the information empty to ignore one of the built in names. Can be repeated
+    #[clap(long = "element-info", value_name = "TAG=INFO", value_parser = parse_element_info)]
+    element_infos: Vec<(String, String)>,
+
+    /// How the element links to others: simple, master, slave, `next_report`, `previous_report`, terminal or thumbnail. Defaults to the `LINK_TYPE` attribute of the drawing
+    #[clap(long, value_name = "TYPE")]
+    link_type: Option<LinkType>,
+
+    /// Sets a kind information of a master, slave or terminal element, e.g. --kind state=NC --kind number=2. Can be repeated
+    #[clap(long = "kind", value_name = "NAME=VALUE", value_parser = parse_kind)]
+    kind_infos: Vec<(String, String)>,
+
+    /// Names the element in a language, e.g. --name fr="Contacteur". Can be repeated. A my_file.names file next to the .dxf, with one LANG=NAME a line, works too
+    #[clap(long = "name", value_name = "LANG=NAME", value_parser = parse_name)]
+    names: Vec<(String, String)>,
+
+    /// Makes the uuids out of the name and contents of the .dxf instead of random ones, so converting the same file again gives the exact same .elmt. For keeping converted libraries under version control
+    #[clap(long)]
+    deterministic: bool,
+
+    /// Writes what happened to each entity to a file, as JSON if it ends in .json and as plain text otherwise
+    #[clap(long, value_name = "FILE")]
+    report: Option<PathBuf>,
+
+    /// Draws the element as SVG into a file. With more than one .dxf the name of each is added on, e.g. out-motor.svg
+    #[clap(long, value_name = "FILE")]
+    preview: Option<PathBuf>,
+
+    /// Draws the element into a PNG file, for thumbnails. With more than one .dxf the name of each is added on, e.g. out-motor.png
+    #[clap(long, value_name = "FILE")]
+    thumbnail: Option<PathBuf>,
+
+    /// The resolution of the thumbnail, in pixels per inch of the element (where 1mm is 2 QET units)
+    #[clap(long, value_parser = parse_dpi, default_value_t = 96.0)]
+    dpi: f64,
+
     /// Determine the number of lines you want each spline to have (more lines = greater resolution)
     #[clap(short, long, value_parser, default_value_t = 20)]
     spline_step: u32,
 
+    /// How far (in drawing units) the outline of a rotated ellipse is allowed to stray from the real ellipse, smaller values give more points
+    #[clap(long, value_parser, default_value_t = dxf2elmt::qelmt::DEFAULT_ELLIPSE_TOLERANCE)]
+    ellipse_tolerance: f64,
+
     /// Toggles information output... defaults to off
     #[clap(short, long, value_parser, default_value_t = false)]
     info: bool,
+
+    /// Maps a custom line type onto a QET line style (normal, dashed, dotted, dashdotted), e.g. --line-type FENCELINE1=dashdotted. Can be repeated
+    #[clap(long = "line-type", value_name = "NAME=STYLE", value_parser = parse_line_type)]
+    line_types: Vec<(String, LineStyle)>,
+
+    /// The smallest lineweight in mm for the QET thin, normal, hight and eleve line weights, anything thinner is drawn without a line
+    #[clap(
+        long = "line-weights",
+        value_name = "THIN,NORMAL,HIGHT,ELEVE",
+        default_value = "0,0.35,0.7,1.4"
+    )]
+    line_weights: LineWeightBreakpoints,
+
+    /// Draw everything in black instead of using the colors from the drawing
+    #[clap(long)]
+    monochrome: bool,
+}
+
+fn parse_line_type(arg: &str) -> Result<(String, LineStyle), String> {
+    let (name, style) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("expected NAME=STYLE, got '{arg}'"))?;
+    Ok((name.trim().to_string(), style.trim().parse()?))
+}
+
+fn parse_dpi(arg: &str) -> Result<f64, String> {
+    match arg.trim().parse::<f64>() {
+        Ok(dpi) if dpi > 0.0 && dpi.is_finite() => Ok(dpi),
+        Ok(_) => Err(format!("the resolution has to be above 0, got '{arg}'")),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+fn parse_element_info(arg: &str) -> Result<(String, String), String> {
+    let (tag, info) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("expected TAG=INFO, got '{arg}'"))?;
+    let info = info.trim();
+    if !info.is_empty() && !ELEMENT_INFO_KEYS.contains(&info) {
+        return Err(format!(
+            "unknown element information '{info}', expected one of {}",
+            ELEMENT_INFO_KEYS.join(", ")
+        ));
+    }
+    //the built in names are upper case, so an override replaces them
+    Ok((tag.trim().to_uppercase(), info.to_string()))
+}
+
+fn parse_kind(arg: &str) -> Result<(String, String), String> {
+    let (name, value) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("expected NAME=VALUE, got '{arg}'"))?;
+    Ok((name.trim().to_string(), value.trim().to_string()))
+}
+
+fn parse_text_layer(arg: &str) -> Result<(String, TextMode), String> {
+    let (layer, mode) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("expected LAYER=MODE, got '{arg}'"))?;
+    Ok((layer.trim().to_string(), mode.trim().parse()?))
 }
 
+//where the preview (or thumbnail) of the .dxf goes, each file gets its own when there's more than one
+fn output_path(output: &Path, dxf: &Path, several_files: bool) -> PathBuf {
+    if !several_files {
+        return output.to_path_buf();
+    }
+
+    let stem = output.file_stem().unwrap_or_default().to_string_lossy();
+    let dxf_stem = dxf.file_stem().unwrap_or_default().to_string_lossy();
+    match output.extension() {
+        Some(ext) => output.with_file_name(format!("{stem}-{dxf_stem}.{}", ext.to_string_lossy())),
+        None => output.with_file_name(format!("{stem}-{dxf_stem}")),
+    }
+}
+
+#[derive(serde::Serialize)]
+struct FileReport<'a> {
+    file: &'a Path,
+    summary: Summary,
+    entities: &'a [EntityReport],
+}
+
+fn write_report(report_file: &Path, reports: &[(PathBuf, Report)]) -> Result<()> {
+    let is_json = report_file
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+
+    let contents = if is_json {
+        let reports: Vec<_> = reports
+            .iter()
+            .map(|(file, report)| FileReport {
+                file,
+                summary: report.summary(),
+                entities: &report.entities,
+            })
+            .collect();
+        serde_json::to_string_pretty(&reports)?
+    } else {
+        reports
+            .iter()
+            .map(|(file, report)| format!("{}\n{report}\n", file.display()))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    std::fs::write(report_file, contents)
+        .with_context(|| format!("Failed to write the report to {}", report_file.display()))
+}
 
 #[allow(clippy::too_many_lines)]
 fn main() -> Result<()> {
@@ -99,12 +305,82 @@ fn main() -> Result<()> {
     
     let options = ConversionOptions {
         spline_step: args.spline_step,
+        ellipse_tolerance: args.ellipse_tolerance,
         verbose: args.verbose,
         info: args.info,
+        line_types: args.line_types.into_iter().collect(),
+        line_weights: args.line_weights,
+        monochrome: args.monochrome,
+        text_mode: if args.dtext {
+            TextMode::Dynamic
+        } else {
+            args.text_mode
+        },
+        text_layers: args.text_layers.into_iter().collect(),
+        font_metrics: !args.no_font_metrics,
+        terminals: args.terminals.then_some(TerminalRules {
+            layers: args.terminal_layers,
+            blocks: args.terminal_blocks,
+            tags: args.terminal_tags,
+            max_radius: args.terminal_radius,
+        }),
+        element_info: default_info_map()
+            .into_iter()
+            .chain(args.element_infos)
+            .collect(),
+        link_type: args.link_type,
+        kind_infos: args.kind_infos.into_iter().collect(),
+        names: args.names.into_iter().collect(),
+        deterministic: args.deterministic,
+        preview: args.preview.is_some(),
+        thumbnail_dpi: args.thumbnail.is_some().then_some(args.dpi),
     };
 
+    let mut reports = Vec::new();
+    let several_files = args.file_names.len() > 1;
     for file_name in args.file_names {
         let result = convert_dxf_file(&file_name, &options)?;
+        if let Some(elmt) = &result.elmt_path {
+            println!(
+                "{} was created from {}",
+                elmt.to_string_lossy(),
+                file_name.to_string_lossy()
+            );
+        }
+
+        if args.report.is_some() {
+            reports.push((file_name.clone(), result.report.clone()));
+        }
+
+        if let (Some(preview), Some(svg)) = (&args.preview, &result.svg_preview) {
+            let preview = output_path(preview, &file_name, several_files);
+            std::fs::write(&preview, svg)
+                .with_context(|| format!("Failed to write the preview to {}", preview.display()))?;
+        }
+
+        //a thumbnail going wrong is only a warning, the .elmt is already there
+        //and the rest of the files still get converted
+        if let Some(thumbnail) = &args.thumbnail {
+            let thumbnail = output_path(thumbnail, &file_name, several_files);
+            match &result.thumbnail {
+                Some(png) => {
+                    if let Err(err) = std::fs::write(&thumbnail, png) {
+                        eprintln!(
+                            "Warning: failed to write the thumbnail to {}: {err}",
+                            thumbnail.display()
+                        );
+                    }
+                }
+                None => eprintln!(
+                    "Warning: could not draw the thumbnail for {}",
+                    file_name.display()
+                ),
+            }
+        }
+
+        for warning in &result.warnings {
+            eprintln!("Warning: {warning}");
+        }
 
         if options.info {
             if let Some(stats) = result.stats {
@@ -120,6 +396,7 @@ fn main() -> Result<()> {
                 println!("Polylines: {}", stats.polylines);
                 println!("LwPolylines: {}", stats.lwpolylines);
                 println!("Solids: {}", stats.solids);
+                println!("Hatches: {}", stats.hatches);
                 println!("Blocks: {}", stats.blocks);
                 println!("Currently Unsupported: {}", stats.unsupported);
                 println!("\nTime Elapsed: {} ms", stats.elapsed_ms);
@@ -127,12 +404,14 @@ fn main() -> Result<()> {
         }
 
         if options.verbose {
-            if let Some(xml) = result.xml_content {
-                print!("{xml}");
-            }
+            print!("{}", result.xml_content);
         }
     }
     drop(dxf_loop_guard);
 
+    if let Some(report_file) = args.report {
+        write_report(&report_file, &reports)?;
+    }
+
     Ok(())
 }
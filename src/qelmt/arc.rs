@@ -1,6 +1,13 @@
-use super::{two_dec, ScaleEntity};
+use super::polygon::Bulge;
+use super::polygon::Coordinate;
+use super::raster::{polyline, Canvas, Paint};
+use super::style::Style;
+use super::svg::{add_style, PatternFills, ToSvg};
+use super::transform::EPSILON;
+use super::{two_dec, BoundingBox, Bounds, Objects, Polygon, Transform, TransformEntity};
 use dxf::entities;
 use simple_xml_builder::XMLElement;
+use tracing::trace;
 
 #[derive(Debug)]
 pub struct Arc {
@@ -12,7 +19,7 @@ pub struct Arc {
     height: f64,
     start: f64,
     angle: f64,
-    style: String,
+    pub(crate) style: Style,
     antialias: bool,
 }
 
@@ -43,12 +50,47 @@ impl From<&entities::Arc> for Arc {
             //in the original code antialias is always set to false...I'm guessing for performance
             //reasons...I'm trying to think if there is a time we might want to turn it on?
             antialias: false,
-            style: if arc.thickness > 0.1 {
-                "line-style:normal;line-weight:normal;filling:none;color:black"
-            } else {
-                "line-style:normal;line-weight:thin;filling:none;color:black"
-            }
-            .into(),
+            style: Style::default(),
+        }
+    }
+}
+
+impl From<(&Bulge, &Style)> for Arc {
+    fn from((bulge, style): (&Bulge, &Style)) -> Self {
+        let center = bulge.center();
+        let radius = bulge.radius();
+
+        Arc {
+            x: center.x - radius,
+            y: -center.y - radius,
+            height: radius * 2.0,
+            width: radius * 2.0,
+            start: bulge.start_angle(),
+            angle: bulge.sweep().to_degrees(),
+
+            //in the original code antialias is always set to false...I'm guessing for performance
+            //reasons...I'm trying to think if there is a time we might want to turn it on?
+            antialias: false,
+            style: style.clone(),
+        }
+    }
+}
+
+impl Arc {
+    //a piece of an axis aligned ellipse centered on the origin
+    pub(crate) fn elliptical(rx: f64, ry: f64, start: f64, sweep: f64, style: Style) -> Self {
+        Arc {
+            x: -rx,
+            y: -ry,
+            width: rx * 2.0,
+            height: ry * 2.0,
+            start: start.rem_euclid(360.0),
+            angle: sweep,
+
+            //in the original code antialias is always set to false...I'm guessing for performance
+            //reasons...I'm trying to think if there is a time we might want to turn it on?
+            antialias: false,
+            style,
         }
     }
 }
@@ -68,27 +110,195 @@ impl From<&Arc> for XMLElement {
     }
 }
 
-impl ScaleEntity for Arc {
-    fn scale(&mut self, fact_x: f64, fact_y: f64) {
-        self.x *= fact_x;
-        self.y *= fact_y;
-        self.width *= fact_x;
-        self.height *= fact_y;
+impl TransformEntity for Arc {
+    fn transform(mut self, tf: &Transform) -> Objects {
+        let (half_w, half_h) = (self.width / 2.0, self.height / 2.0);
+
+        if !(tf.keeps_axes() || (tf.is_uniform() && (self.width - self.height).abs() < EPSILON)) {
+            //A QET arc is a piece of an axis aligned ellipse, so once it's rotated (or
+            //a circular arc is skewed) the only option left is to tessellate it
+            trace!("Arc can't stay axis aligned under {tf:?}, converting to a polygon");
+            let (cx, cy) = (self.x + half_w, self.y + half_h);
+            let coordinates = Transform::tessellate(self.start, self.angle)
+                .into_iter()
+                .map(|(cos, sin)| {
+                    let (x, y) = tf.apply_qet(cx + half_w * cos, cy - half_h * sin);
+                    Coordinate { x, y }
+                })
+                .collect();
+
+            return Objects::Polygon(Polygon::new(coordinates, false, self.style));
+        }
+
+        let (cx, cy) = tf.apply_qet(self.x + half_w, self.y + half_h);
+        let (rx, ry) = tf.radii(half_w, half_h);
+
+        //the start and span are parametric angles on the ellipse, so map the
+        //point at each end through the transform and find its new angle
+        let map_angle = |deg: f64| {
+            let (sin, cos) = deg.to_radians().sin_cos();
+            let (vx, vy) = tf.apply_vector(half_w * cos, half_h * sin);
+            (vy / ry.max(f64::MIN_POSITIVE))
+                .atan2(vx / rx.max(f64::MIN_POSITIVE))
+                .to_degrees()
+        };
+
+        //a mirror flips the direction of the arc, so it then starts from the old end
+        self.start = if tf.is_mirrored() {
+            map_angle(self.start + self.angle)
+        } else {
+            map_angle(self.start)
+        }
+        .rem_euclid(360.0);
+
+        self.x = cx - rx;
+        self.y = cy - ry;
+        self.width = rx * 2.0;
+        self.height = ry * 2.0;
+        Objects::Arc(self)
+    }
+}
+
+impl Bounds for Arc {
+    //only the part of the ellipse that's swept counts, which is the two ends
+    //plus any of the points straight left, right, up or down from the center it passes
+    fn bounding_box(&self) -> BoundingBox {
+        let (rx, ry) = (self.width / 2.0, self.height / 2.0);
+        let (cx, cy) = (self.x + rx, self.y + ry);
+        let point = |deg: f64| {
+            let (sin, cos) = deg.to_radians().sin_cos();
+            (cx + rx * cos, cy - ry * sin)
+        };
+
+        let (from, to) = if self.angle < 0.0 {
+            (self.start + self.angle, self.start)
+        } else {
+            (self.start, self.start + self.angle)
+        };
+        if to - from >= 360.0 {
+            return BoundingBox::from_points([0.0, 90.0, 180.0, 270.0].map(point));
+        }
+
+        let first_quadrant = (from / 90.0).ceil();
+        let extremes = (0..4)
+            .map(|i| (first_quadrant + f64::from(i)) * 90.0)
+            .take_while(|deg| *deg <= to);
+        BoundingBox::from_points([from, to].into_iter().chain(extremes).map(point))
+    }
+}
+
+impl ToSvg for Arc {
+    fn to_svg(&self, fills: &mut PatternFills) -> XMLElement {
+        let (rx, ry) = (self.width / 2.0, self.height / 2.0);
+        let (cx, cy) = (self.x + rx, self.y + ry);
+        //QET only keeps whole degrees
+        let (start, angle) = (self.start.round(), self.angle.round());
+        let point = |deg: f64| {
+            let (sin, cos) = deg.to_radians().sin_cos();
+            (two_dec(cx + rx * cos), two_dec(cy - ry * sin))
+        };
+
+        //a full turn has to be split in two, an SVG arc that ends where it starts isn't drawn
+        let d = if angle.abs() >= 360.0 {
+            let ((x1, y1), (x2, y2)) = (point(start), point(start + 180.0));
+            format!("M{x1},{y1} A{rx},{ry} 0 0 0 {x2},{y2} A{rx},{ry} 0 0 0 {x1},{y1}")
+        } else {
+            let ((x1, y1), (x2, y2)) = (point(start), point(start + angle));
+            //QET angles go counter clockwise, which is the negative direction in SVG with y going down
+            let large = u8::from(angle.abs() > 180.0);
+            let sweep = u8::from(angle < 0.0);
+            format!("M{x1},{y1} A{rx},{ry} 0 {large} {sweep} {x2},{y2}")
+        };
+
+        let mut arc_svg = XMLElement::new("path");
+        arc_svg.add_attribute("d", d);
+        add_style(&mut arc_svg, &self.style, fills);
+        arc_svg
+    }
+}
+
+impl Paint for Arc {
+    fn paint(&self, canvas: &mut Canvas) {
+        let (rx, ry) = (self.width / 2.0, self.height / 2.0);
+        let (cx, cy) = (self.x + rx, self.y + ry);
+        let (start, angle) = (self.start.round(), self.angle.round().clamp(-360.0, 360.0));
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let steps = (angle.abs() / ARC_PAINT_STEP_DEG).ceil().max(1.0) as usize;
+        let points = (0..=steps).map(|i| {
+            #[allow(clippy::cast_precision_loss)]
+            let (sin, cos) = (start + angle * i as f64 / steps as f64)
+                .to_radians()
+                .sin_cos();
+            (cx + rx * cos, cy - ry * sin)
+        });
+        canvas.shape(polyline(points, false), &self.style);
+    }
+}
+
+//small enough that the straight bits don't show in a thumbnail
+const ARC_PAINT_STEP_DEG: f64 = 2.0;
+
+#[cfg(test)]
+mod tests {
+    use super::{Arc, Style};
+    use crate::qelmt::{BoundingBox, Bounds};
+
+    //a unit circle around the origin, in QET coordinates so up is -y
+    fn arc(start: f64, angle: f64) -> Arc {
+        Arc {
+            x: -1.0,
+            y: -1.0,
+            width: 2.0,
+            height: 2.0,
+            start,
+            angle,
+            style: Style::default(),
+            antialias: false,
+        }
+    }
+
+    fn close(bbox: &BoundingBox, left: f64, top: f64, right: f64, bottom: f64) -> bool {
+        [
+            (bbox.left, left),
+            (bbox.top, top),
+            (bbox.right, right),
+            (bbox.bottom, bottom),
+        ]
+        .iter()
+        .all(|(a, b)| (a - b).abs() < 1e-9)
+    }
+
+    #[test]
+    fn quarter_arc_is_just_its_ends() {
+        let bbox = arc(0.0, 90.0).bounding_box();
+        assert!(close(&bbox, 0.0, -1.0, 1.0, 0.0));
     }
 
-    fn left_bound(&self) -> f64 {
-        self.x
+    #[test]
+    fn arc_over_the_top_takes_in_the_top() {
+        let half = std::f64::consts::FRAC_1_SQRT_2;
+        let bbox = arc(45.0, 90.0).bounding_box();
+        assert!(close(&bbox, -half, -1.0, half, -half));
     }
 
-    fn right_bound(&self) -> f64 {
-        self.x + self.width
+    #[test]
+    fn arc_through_zero_degrees_takes_in_the_right() {
+        let (sin, cos) = 10_f64.to_radians().sin_cos();
+        let bbox = arc(350.0, 20.0).bounding_box();
+        assert!(close(&bbox, cos, -sin, 1.0, sin));
     }
 
-    fn top_bound(&self) -> f64 {
-        self.y
+    #[test]
+    fn clockwise_arc_is_swept_backwards() {
+        //from 300° back to 180°, through the bottom
+        let bbox = arc(300.0, -120.0).bounding_box();
+        assert!(close(&bbox, -1.0, 0.0, 300_f64.to_radians().cos(), 1.0));
     }
 
-    fn bot_bound(&self) -> f64 {
-        self.y + self.height
+    #[test]
+    fn three_quarters_takes_in_every_side() {
+        let bbox = arc(270.0, 270.0).bounding_box();
+        assert!(close(&bbox, -1.0, -1.0, 1.0, 1.0));
     }
 }
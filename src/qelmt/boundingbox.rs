@@ -0,0 +1,75 @@
+/// An axis aligned box around part of the element, in QET coordinates (so y grows downwards)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub left: f64,
+    pub top: f64,
+    pub right: f64,
+    pub bottom: f64,
+}
+
+impl BoundingBox {
+    /// A box around nothing, which doesn't change any box it's combined with
+    pub const EMPTY: Self = Self {
+        left: f64::INFINITY,
+        top: f64::INFINITY,
+        right: f64::NEG_INFINITY,
+        bottom: f64::NEG_INFINITY,
+    };
+
+    pub fn from_points(points: impl IntoIterator<Item = (f64, f64)>) -> Self {
+        points
+            .into_iter()
+            .fold(Self::EMPTY, |bbox, (x, y)| bbox.include(x, y))
+    }
+
+    /// The box grown to take in the point
+    #[must_use]
+    pub fn include(self, x: f64, y: f64) -> Self {
+        Self {
+            left: self.left.min(x),
+            top: self.top.min(y),
+            right: self.right.max(x),
+            bottom: self.bottom.max(y),
+        }
+    }
+
+    /// The box around both boxes
+    #[must_use]
+    pub fn union(self, other: &Self) -> Self {
+        Self {
+            left: self.left.min(other.left),
+            top: self.top.min(other.top),
+            right: self.right.max(other.right),
+            bottom: self.bottom.max(other.bottom),
+        }
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.left > self.right || self.top > self.bottom
+    }
+
+    #[must_use]
+    pub fn width(&self) -> f64 {
+        if self.is_empty() {
+            0.0
+        } else {
+            self.right - self.left
+        }
+    }
+
+    #[must_use]
+    pub fn height(&self) -> f64 {
+        if self.is_empty() {
+            0.0
+        } else {
+            self.bottom - self.top
+        }
+    }
+}
+
+impl Default for BoundingBox {
+    fn default() -> Self {
+        Self::EMPTY
+    }
+}
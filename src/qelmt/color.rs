@@ -0,0 +1,107 @@
+use hex_color::HexColor;
+
+//The brightness levels for each of the 24 hues in the AutoCAD Color Index,
+//every one comes in a fully saturated version and a lighter one
+const ACI_VALUES: [f64; 5] = [255.0, 189.0, 129.0, 104.0, 79.0];
+const ACI_GRAYS: [u8; 6] = [0x33, 0x50, 0x69, 0x82, 0xBE, 0xFF];
+
+/// The RGB color for an index of the AutoCAD Color Index (ACI).
+/// 0 (BYBLOCK) and 256 (BYLAYER) aren't real colors and have to be resolved before getting here
+#[must_use]
+pub fn aci_to_rgb(index: u8) -> HexColor {
+    match index {
+        1 => HexColor::rgb(255, 0, 0),
+        2 => HexColor::rgb(255, 255, 0),
+        3 => HexColor::rgb(0, 255, 0),
+        4 => HexColor::rgb(0, 255, 255),
+        5 => HexColor::rgb(0, 0, 255),
+        6 => HexColor::rgb(255, 0, 255),
+        8 => HexColor::rgb(128, 128, 128),
+        9 => HexColor::rgb(192, 192, 192),
+        10..=249 => {
+            //the hues go around the color wheel in steps of 15 degrees starting at red
+            let hue = f64::from(index / 10 - 1) * 15.0;
+            let value = ACI_VALUES[usize::from(index % 10 / 2)];
+            let saturation = if index % 2 == 0 { 1.0 } else { 1.0 / 3.0 };
+            hsv_to_rgb(hue, saturation, value)
+        }
+        250..=255 => {
+            let gray = ACI_GRAYS[usize::from(index - 250)];
+            HexColor::rgb(gray, gray, gray)
+        }
+        //7 is white on a dark background and black on a light one, QET is always light
+        _ => HexColor::BLACK,
+    }
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn hsv_to_rgb(hue: f64, saturation: f64, value: f64) -> HexColor {
+    let sector = (hue / 60.0).floor();
+    let frac = hue / 60.0 - sector;
+    let min = value * (1.0 - saturation);
+    let falling = value * (1.0 - saturation * frac);
+    let rising = value * (1.0 - saturation * (1.0 - frac));
+
+    let (r, g, b) = match sector as u8 {
+        0 => (value, rising, min),
+        1 => (falling, value, min),
+        2 => (min, value, rising),
+        3 => (min, falling, value),
+        4 => (rising, min, value),
+        _ => (value, min, falling),
+    };
+    HexColor::rgb(r as u8, g as u8, b as u8)
+}
+
+//The named colors the QET element editor offers for the style attribute, the values are
+//the QColors QET draws them with. White is left out on purpose, the element is drawn on a white
+//background so anything white in the drawing would just disappear
+const QET_COLORS: [(&str, HexColor); 12] = [
+    ("black", HexColor::rgb(0, 0, 0)),
+    ("red", HexColor::rgb(255, 0, 0)),
+    ("green", HexColor::rgb(0, 255, 0)),
+    ("blue", HexColor::rgb(0, 0, 255)),
+    ("gray", HexColor::rgb(160, 160, 164)),
+    ("lightgray", HexColor::rgb(192, 192, 192)),
+    ("brun", HexColor::rgb(97, 44, 0)),
+    ("yellow", HexColor::rgb(255, 255, 0)),
+    ("cyan", HexColor::rgb(0, 255, 255)),
+    ("magenta", HexColor::rgb(255, 0, 255)),
+    ("orange", HexColor::rgb(255, 128, 0)),
+    ("purple", HexColor::rgb(136, 28, 168)),
+];
+
+/// The closest of the QET named colors, since the geometry styles can't take an arbitrary color
+#[must_use]
+pub fn qet_color_name(color: HexColor) -> &'static str {
+    QET_COLORS
+        .iter()
+        .min_by(|(_, a), (_, b)| color_distance(color, *a).total_cmp(&color_distance(color, *b)))
+        .map_or("black", |(name, _)| name)
+}
+
+/// The color QET draws one of the color names in the style attribute with, including
+/// the white the hatches cut their holes with. Anything it doesn't know is black
+#[must_use]
+pub fn named_color(name: &str) -> HexColor {
+    if name == "white" {
+        return HexColor::WHITE;
+    }
+    QET_COLORS
+        .iter()
+        .find_map(|(qet_name, color)| (*qet_name == name).then_some(*color))
+        .unwrap_or(HexColor::BLACK)
+}
+
+//"redmean" weighted distance, cheap and a lot closer to how we see the difference
+//between two colors than the plain euclidean distance
+fn color_distance(a: HexColor, b: HexColor) -> f64 {
+    let (r1, g1, b1) = a.split_rgb();
+    let (r2, g2, b2) = b.split_rgb();
+    let rmean = (f64::from(r1) + f64::from(r2)) / 2.0;
+    let dr = f64::from(r1) - f64::from(r2);
+    let dg = f64::from(g1) - f64::from(g2);
+    let db = f64::from(b1) - f64::from(b2);
+    ((2.0 + rmean / 256.0) * dr * dr + 4.0 * dg * dg + (2.0 + (255.0 - rmean) / 256.0) * db * db)
+        .sqrt()
+}
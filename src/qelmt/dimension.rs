@@ -0,0 +1,265 @@
+use super::dynamictext::DTextBuilder;
+use super::{Arc, Line, Objects, Text, TextMode};
+use dxf::entities::{
+    self, AngularThreePointDimension, DiameterDimension, DimensionBase, EntityType, MText,
+    OrdinateDimension, RadialDimension, RotatedDimension,
+};
+use dxf::enums::{AttachmentPoint, DimensionType};
+use dxf::Point;
+use hex_color::HexColor;
+
+//The bits of the dimension style needed to draw a dimension ourselves,
+//when the drawing doesn't come with the block for it
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct DimStyle {
+    pub text_height: f64,
+    pub arrow_size: f64,
+    pub decimal_places: usize,
+    pub angle_decimal_places: usize,
+}
+
+impl Default for DimStyle {
+    //the ISO-25 values
+    fn default() -> Self {
+        Self {
+            text_height: 2.5,
+            arrow_size: 2.5,
+            decimal_places: 2,
+            angle_decimal_places: 0,
+        }
+    }
+}
+
+/// The common part of all the dimension entities
+pub(crate) fn dimension_base(ent: &EntityType) -> Option<&DimensionBase> {
+    match ent {
+        EntityType::RotatedDimension(dim) => Some(&dim.dimension_base),
+        EntityType::RadialDimension(dim) => Some(&dim.dimension_base),
+        EntityType::DiameterDimension(dim) => Some(&dim.dimension_base),
+        EntityType::AngularThreePointDimension(dim) => Some(&dim.dimension_base),
+        EntityType::OrdinateDimension(dim) => Some(&dim.dimension_base),
+        _ => None,
+    }
+}
+
+/// A dimension drawn from its definition points, for when the drawing is missing the
+/// anonymous block that normally holds what the dimension looks like
+pub(crate) struct Dimension(pub Vec<Objects>);
+
+impl Dimension {
+    pub fn fallback(
+        ent: &EntityType,
+        style: &DimStyle,
+        text_color: HexColor,
+        text_mode: TextMode,
+        font_metrics: bool,
+    ) -> Option<Self> {
+        let mut dim = DimensionDrawer {
+            objects: Vec::new(),
+            style,
+            text_color,
+            text_mode,
+            font_metrics,
+        };
+        match ent {
+            EntityType::RotatedDimension(rot) => dim.linear(rot),
+            EntityType::RadialDimension(rad) => dim.radial(rad),
+            EntityType::DiameterDimension(dia) => dim.diameter(dia),
+            EntityType::AngularThreePointDimension(ang) => dim.angular(ang),
+            EntityType::OrdinateDimension(ord) => dim.ordinate(ord),
+            _ => return None,
+        }
+        Some(Dimension(dim.objects))
+    }
+}
+
+struct DimensionDrawer<'a> {
+    objects: Vec<Objects>,
+    style: &'a DimStyle,
+    text_color: HexColor,
+    text_mode: TextMode,
+    font_metrics: bool,
+}
+
+impl DimensionDrawer<'_> {
+    fn line(&mut self, p1: Point, p2: Point, arrow_start: bool, arrow_end: bool) {
+        let line: Line = (&entities::Line::new(p1, p2)).into();
+        self.objects.push(Objects::Line(line.arrows(
+            arrow_start,
+            arrow_end,
+            self.style.arrow_size,
+        )));
+    }
+
+    //The text of a dimension can override the measurement. Empty means just the measurement,
+    //"<>" gets replaced with it, and a single space hides the text
+    fn text(&mut self, base: &DimensionBase, measurement: &str, rotation: f64) {
+        let value = match base.text.as_str() {
+            "" => measurement.to_string(),
+            " " => return,
+            text => text.replace("<>", measurement),
+        };
+
+        //the special characters AutoCAD writes as control codes
+        let value =
+            [("%%c", "Ø"), ("%%d", "°"), ("%%p", "±")]
+                .iter()
+                .fold(value, |val, (code, chr)| {
+                    val.replace(code, chr)
+                        .replace(&code.to_ascii_uppercase(), chr)
+                });
+
+        let mtext = MText {
+            insertion_point: base.text_mid_point.clone(),
+            initial_text_height: self.style.text_height,
+            attachment_point: AttachmentPoint::MiddleCenter,
+            rotation_angle: rotation,
+            text: value,
+            ..Default::default()
+        };
+        self.objects.push(match self.text_mode {
+            TextMode::Static => {
+                let mut text: Text = (&mtext, self.text_color).into();
+                text.font_metrics = self.font_metrics;
+                Objects::Text(text)
+            }
+            TextMode::Dynamic => Objects::DynamicText(
+                DTextBuilder::from_mtext(&mtext)
+                    .color(self.text_color)
+                    .font_metrics(self.font_metrics)
+                    .build(),
+            ),
+        });
+    }
+
+    fn measurement(&self, base: &DimensionBase, measured: f64) -> String {
+        let value = if base.actual_measurement > 0.0 {
+            base.actual_measurement
+        } else {
+            measured
+        };
+        format!("{value:.prec$}", prec = self.style.decimal_places)
+    }
+
+    fn linear(&mut self, dim: &RotatedDimension) {
+        let base = &dim.dimension_base;
+        let (ext1, ext2) = (&dim.definition_point_2, &dim.definition_point_3);
+
+        //an aligned dimension runs parallel to the points it measures,
+        //a rotated one at its own angle
+        let angle = if base.dimension_type == DimensionType::Aligned {
+            (ext2.y - ext1.y).atan2(ext2.x - ext1.x).to_degrees()
+        } else {
+            dim.rotation_angle
+        };
+        let (sin, cos) = angle.to_radians().sin_cos();
+
+        //the dimension line goes through the first definition point, the ends of it
+        //are where the extension lines from the measured points meet it
+        let on_line = |pt: &Point| {
+            let dist =
+                (pt.x - base.definition_point_1.x) * cos + (pt.y - base.definition_point_1.y) * sin;
+            Point::new(
+                base.definition_point_1.x + dist * cos,
+                base.definition_point_1.y + dist * sin,
+                0.0,
+            )
+        };
+        let (end1, end2) = (on_line(ext1), on_line(ext2));
+
+        self.line(ext1.clone(), end1.clone(), false, false);
+        self.line(ext2.clone(), end2.clone(), false, false);
+        let length = (end2.x - end1.x).hypot(end2.y - end1.y);
+        self.line(end1, end2, true, true);
+
+        let measurement = self.measurement(base, length);
+        self.text(base, &measurement, readable_angle(angle));
+    }
+
+    fn radial(&mut self, dim: &RadialDimension) {
+        let base = &dim.dimension_base;
+        let (center, pt) = (&base.definition_point_1, &dim.definition_point_2);
+        self.line(center.clone(), pt.clone(), false, true);
+
+        let radius = (pt.x - center.x).hypot(pt.y - center.y);
+        let measurement = format!("R{}", self.measurement(base, radius));
+        self.text(base, &measurement, 0.0);
+    }
+
+    fn diameter(&mut self, dim: &DiameterDimension) {
+        let base = &dim.dimension_base;
+        let (pt1, pt2) = (&base.definition_point_1, &dim.definition_point_2);
+        self.line(pt1.clone(), pt2.clone(), true, true);
+
+        let diameter = (pt2.x - pt1.x).hypot(pt2.y - pt1.y);
+        let measurement = format!("Ø{}", self.measurement(base, diameter));
+        self.text(base, &measurement, 0.0);
+    }
+
+    fn angular(&mut self, dim: &AngularThreePointDimension) {
+        let base = &dim.dimension_base;
+        let vertex = &dim.definition_point_4;
+        let (pt1, pt2) = (&dim.definition_point_2, &dim.definition_point_3);
+        let arc_pt = &base.definition_point_1;
+        let radius = (arc_pt.x - vertex.x).hypot(arc_pt.y - vertex.y);
+
+        let angle_to = |pt: &Point| (pt.y - vertex.y).atan2(pt.x - vertex.x).to_degrees();
+        let (start, end) = (angle_to(pt1), angle_to(pt2));
+
+        //extension lines out from the measured points to the dimension arc
+        for (pt, angle) in [(pt1, start), (pt2, end)] {
+            let (sin, cos) = angle.to_radians().sin_cos();
+            let on_arc = Point::new(vertex.x + radius * cos, vertex.y + radius * sin, 0.0);
+            self.line(pt.clone(), on_arc, false, false);
+        }
+
+        let arc: Arc = (&entities::Arc::new(
+            vertex.clone(),
+            radius,
+            start.rem_euclid(360.0),
+            end.rem_euclid(360.0),
+        ))
+            .into();
+        self.objects.push(Objects::Arc(arc));
+
+        let measured = (end - start).rem_euclid(360.0);
+        let measurement = if base.actual_measurement > 0.0 {
+            //stored in radians for angular dimensions
+            base.actual_measurement.to_degrees()
+        } else {
+            measured
+        };
+        //angles have their own precision (DIMADEC)
+        let measurement = format!(
+            "{measurement:.prec$}°",
+            prec = self.style.angle_decimal_places
+        );
+        self.text(base, &measurement, 0.0);
+    }
+
+    fn ordinate(&mut self, dim: &OrdinateDimension) {
+        let base = &dim.dimension_base;
+        let (feature, leader_end) = (&dim.definition_point_2, &dim.definition_point_3);
+        self.line(feature.clone(), leader_end.clone(), false, false);
+
+        let origin = &base.definition_point_1;
+        let measured = if base.is_ordinate_x_type {
+            feature.x - origin.x
+        } else {
+            feature.y - origin.y
+        };
+        let measurement = self.measurement(base, measured.abs());
+        let rotation = if base.is_ordinate_x_type { 90.0 } else { 0.0 };
+        self.text(base, &measurement, rotation);
+    }
+}
+
+//keeps the text from ending up upside down
+fn readable_angle(angle: f64) -> f64 {
+    let angle = angle.rem_euclid(360.0);
+    if angle > 90.0 && angle <= 270.0 {
+        angle - 180.0
+    } else {
+        angle
+    }
+}
@@ -1,8 +1,12 @@
-use super::{two_dec, FontInfo, ScaleEntity, TextEntity};
+use super::metrics::TextSize;
+use super::raster::{Canvas, Paint};
+use super::svg::{text_lines, PatternFills, ToSvg};
+use super::{
+    two_dec, BoundingBox, Bounds, FontInfo, Objects, TextEntity, Transform, TransformEntity,
+};
 use dxf::entities::{self, AttributeDefinition};
 use hex_color::HexColor;
 use simple_xml_builder::XMLElement;
-use unicode_segmentation::UnicodeSegmentation;
 use uuid::Uuid;
 
 /*use parley::{
@@ -15,7 +19,7 @@ use super::{HAlignment, VAlignment};
 // Normaliza cadenas MTEXT (DXF) eliminando códigos de formato y aplicando saltos de línea.
 // Maneja casos comunes: \P (newline), \f...\; (fuente), \H...\; (altura),
 // \W...\; (ancho), \~ (espacio), \\ (barra invertida literal), \S...\; (apilados -> texto plano).
-fn normalize_mtext(input: &str) -> String {
+pub(crate) fn normalize_mtext(input: &str) -> String {
     let mut out = String::with_capacity(input.len());
     let mut chars = input.chars().peekable();
 
@@ -91,7 +95,7 @@ fn normalize_mtext(input: &str) -> String {
 // Ejemplos:
 //   {\fGaramond|b0|i1|c0|p18;Sofrel}  -> "Garamond"
 //   {\fSwis721 BlkEx BT|b0|i0|c0|p34;RS485i} -> "Swis721 BlkEx BT"
-fn extract_mtext_font(input: &str) -> Option<String> {
+pub(crate) fn extract_mtext_font(input: &str) -> Option<String> {
     let bytes = input.as_bytes();
     let mut i = 0usize;
     while i + 2 < bytes.len() {
@@ -126,7 +130,7 @@ pub struct DynamicText {
     pub y: f64,
     z: f64,
     rotation: f64,
-    uuid: Uuid,
+    pub(crate) uuid: Uuid,
     h_alignment: HAlignment,
     font: FontInfo,
     text_from: String,
@@ -136,6 +140,24 @@ pub struct DynamicText {
     keep_visual_rotation: bool,
     color: HexColor,
     reference_rectangle_width: f64,
+    font_metrics: bool,
+}
+
+impl DynamicText {
+    fn size(&self) -> TextSize {
+        TextSize::measure(&self.text, &self.font, self.font_metrics)
+    }
+
+    //where the baseline of the first line is, placed the same way as the bounding box
+    fn first_baseline(&self) -> f64 {
+        let height = self.size().height;
+        let top = match self.v_alignment {
+            VAlignment::Top => self.y,
+            VAlignment::Center => self.y - height / 2.0,
+            VAlignment::Bottom => self.y - height,
+        };
+        top + self.font.point_size
+    }
 }
 
 impl From<&DynamicText> for XMLElement {
@@ -163,13 +185,12 @@ impl From<&DynamicText> for XMLElement {
         // it's just annoying if the value for "reference_rectangle_width" in the dxf is “0.0”...
         //
         // o.k. ... as long as we do not know the real width:
-        // "guess" the width by number of characters and font-size:
+        // measure it with the font, or "guess" it by number of characters and font-size:
         //
-        let graphene_count = txt.text.graphemes(true).count();
         let txt_width = if txt.reference_rectangle_width > 2.0 {
             txt.reference_rectangle_width
         } else {
-            (graphene_count as f64) * pt_size * 0.75
+            txt.size().width
         };
 
         let x_pos = {
@@ -195,17 +216,16 @@ impl From<&DynamicText> for XMLElement {
         dtxt_xml.add_attribute("text_width", txt.text_width);
         dtxt_xml.add_attribute("color", txt.color.display_rgb());
 
-        //If I ever add support for other text_from types, element and composite text
-        //I'll need to add more smarts here, as there may be some other children components
-        //for now since it only supports user_text I'm just statically adding the single child
-        //component needed
-        //match txt.text_from
+        //the text is kept for the element info texts too, QET shows it in the
+        //element editor. Composite texts would need a <composite_text> child
         let mut text_xml = XMLElement::new("text");
         text_xml.add_text(&txt.text);
         dtxt_xml.add_child(text_xml);
 
         if let Some(i_name) = &txt.info_name {
-            dtxt_xml.add_attribute("info_name", i_name);
+            let mut info_xml = XMLElement::new("info_name");
+            info_xml.add_text(i_name);
+            dtxt_xml.add_child(info_xml);
         }
 
         if txt.keep_visual_rotation {
@@ -216,36 +236,90 @@ impl From<&DynamicText> for XMLElement {
     }
 }
 
-impl ScaleEntity for DynamicText {
-    fn scale(&mut self, fact_x: f64, fact_y: f64) {
-        self.x *= fact_x;
-        self.y *= fact_y;
-        //self.font.pixel_size *= fact;
-        self.font.point_size *= fact_x;
+impl TransformEntity for DynamicText {
+    fn transform(mut self, tf: &Transform) -> Objects {
+        (self.x, self.y) = tf.apply_qet(self.x, self.y);
+        self.rotation = tf.text_rotation_qet(self.rotation);
+        //QET can't stretch text, so a non uniform scale only changes the height
+        self.font.point_size *= tf.scale_y();
+        Objects::DynamicText(self)
     }
+}
 
-    fn left_bound(&self) -> f64 {
-        self.x
+impl Bounds for DynamicText {
+    fn bounding_box(&self) -> BoundingBox {
+        self.size().extents(
+            self.x,
+            self.y,
+            &self.h_alignment,
+            &self.v_alignment,
+            self.rotation,
+        )
     }
+}
 
-    fn right_bound(&self) -> f64 {
-        //todo!()
-        1.0
-    }
+impl ToSvg for DynamicText {
+    //placed the same way as the bounding box, the alignments say where (x, y) is on the text
+    fn to_svg(&self, _fills: &mut PatternFills) -> XMLElement {
+        let mut txt_svg = XMLElement::new("text");
+        txt_svg.add_attribute("font-family", &self.font.family);
+        txt_svg.add_attribute("font-size", two_dec(self.font.point_size));
+        txt_svg.add_attribute("fill", self.color.display_rgb());
+        txt_svg.add_attribute(
+            "text-anchor",
+            match self.h_alignment {
+                HAlignment::Left => "start",
+                HAlignment::Center => "middle",
+                HAlignment::Right => "end",
+            },
+        );
+        if self.rotation != 0.0 {
+            txt_svg.add_attribute(
+                "transform",
+                format!(
+                    "rotate({} {} {})",
+                    two_dec(self.rotation),
+                    two_dec(self.x),
+                    two_dec(self.y)
+                ),
+            );
+        }
 
-    fn top_bound(&self) -> f64 {
-        self.y
+        text_lines(
+            &mut txt_svg,
+            &self.text,
+            self.x,
+            self.first_baseline(),
+            self.font.point_size * TextSize::LINE_SPACING,
+        );
+        txt_svg
     }
+}
 
-    fn bot_bound(&self) -> f64 {
-        //todo!()
-        1.0
+impl Paint for DynamicText {
+    fn paint(&self, canvas: &mut Canvas) {
+        let anchor = match self.h_alignment {
+            HAlignment::Left => 0.0,
+            HAlignment::Center => 0.5,
+            HAlignment::Right => 1.0,
+        };
+        canvas.text(
+            &self.text,
+            &self.font,
+            (self.x, self.first_baseline()),
+            anchor,
+            self.rotation,
+            (self.x, self.y),
+            self.color,
+        );
     }
 }
 
 pub struct DTextBuilder<'a> {
     text: TextEntity<'a>,
     color: Option<HexColor>,
+    font_metrics: bool,
+    info_name: Option<String>,
 }
 
 impl<'a> DTextBuilder<'a> {
@@ -253,6 +327,8 @@ impl<'a> DTextBuilder<'a> {
         Self {
             text: TextEntity::Text(text),
             color: None,
+            font_metrics: true,
+            info_name: None,
         }
     }
 
@@ -260,6 +336,8 @@ impl<'a> DTextBuilder<'a> {
         Self {
             text: TextEntity::MText(text),
             color: None,
+            font_metrics: true,
+            info_name: None,
         }
     }
 
@@ -267,6 +345,8 @@ impl<'a> DTextBuilder<'a> {
         Self {
             text: TextEntity::Attrib(attrib),
             color: None,
+            font_metrics: true,
+            info_name: None,
         }
     }
 
@@ -277,6 +357,19 @@ impl<'a> DTextBuilder<'a> {
         }
     }
 
+    //measure the text with the font for its size, instead of guessing from the number of characters
+    pub fn font_metrics(self, font_metrics: bool) -> Self {
+        Self {
+            font_metrics,
+            ..self
+        }
+    }
+
+    //show the element information (label, comment...) instead of the text, QET fills it in
+    pub fn info_name(self, info_name: Option<String>) -> Self {
+        Self { info_name, ..self }
+    }
+
     pub fn build(self) -> DynamicText {
         let (
             x,
@@ -395,11 +488,8 @@ impl<'a> DTextBuilder<'a> {
             x,
             y,
             z,
-            rotation: if rotation.abs().round() as i64 % 360 != 0 {
-                rotation - 180.0
-            } else {
-                0.0
-            },
+            //dxf rotates counter clockwise, QET clockwise
+            rotation: -rotation,
             uuid: Uuid::new_v4(),
             font: {
                 let mut f = if style_name == "STANDARD" {
@@ -422,14 +512,19 @@ impl<'a> DTextBuilder<'a> {
             reference_rectangle_width, //liest aus der dxf-Datei!!!
             h_alignment,
             v_alignment,
-            text_from: "UserText".into(),
+            text_from: if self.info_name.is_some() {
+                "ElementInfo".into()
+            } else {
+                "UserText".into()
+            },
             frame: false,
             text_width: -1,
             color: self.color.unwrap_or(HexColor::BLACK),
 
             text: value,
             keep_visual_rotation: false,
-            info_name: None,
+            info_name: self.info_name,
+            font_metrics: self.font_metrics,
         }
     }
 }
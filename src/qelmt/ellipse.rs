@@ -1,12 +1,21 @@
-use super::{two_dec, Circularity, ScaleEntity};
+use super::error::ConversionError;
+use super::polygon::Coordinate;
+use super::raster::{Canvas, Paint};
+use super::style::Style;
+use super::svg::{add_style, PatternFills, ToSvg};
+use super::transform::EPSILON;
+use super::{
+    two_dec, Arc, BoundingBox, Bounds, Circularity, Objects, Polygon, Transform, TransformEntity,
+};
 use dxf::entities::{self, Circle, LwPolyline, Polyline};
 use simple_xml_builder::XMLElement;
+use tracing::trace;
 
 #[derive(Debug)]
 pub struct Ellipse {
     height: f64,
     width: f64,
-    style: String,
+    pub(crate) style: Style,
 
     //need to brush up on my Rust scoping rules, isn't there a way to make this pub to just the module?
     pub x: f64,
@@ -26,38 +35,123 @@ impl From<&Circle> for Ellipse {
             //in the original code antialias is always set to false...I'm guessing for performance
             //reasons...I'm trying to think if there is a time we might want to turn it on?
             antialias: false,
-            style: if circ.thickness > 0.5 {
-                "line-style:normal;line-weight:normal;filling:none;color:black"
-            } else {
-                "line-style:normal;line-weight:thin;filling:none;color:black"
-            }
-            .into(),
+            style: Style::default(),
         }
     }
 }
 
-impl From<&entities::Ellipse> for Ellipse {
-    fn from(ellipse: &entities::Ellipse) -> Self {
-        Ellipse {
-            x: ellipse.center.x - ellipse.major_axis.x,
-            y: -ellipse.center.y - ellipse.major_axis.x * ellipse.minor_axis_ratio,
-            height: ellipse.major_axis.x * 2.0,
-            width: ellipse.major_axis.x * 2.0 * ellipse.minor_axis_ratio,
+/// A dxf ellipse, which unlike the QET one can be rotated and only be part of an ellipse.
+/// The parameters are the angles (in radians) on the ellipse before it's squashed by the
+/// minor axis ratio, the same as the parametric angles a QET arc uses
+pub(crate) struct DxfEllipse<'a> {
+    pub ellipse: &'a entities::Ellipse,
 
-            //in the original code antialias is always set to false...I'm guessing for performance
-            //reasons...I'm trying to think if there is a time we might want to turn it on?
-            antialias: false,
-            style: "line-style:normal;line-weight:thin;filling:none;color:black".into(),
+    //the furthest the tessellated outline can be from the actual ellipse, in drawing units
+    pub tolerance: f64,
+}
+
+impl DxfEllipse<'_> {
+    pub fn to_objects(&self, tf: &Transform) -> Objects {
+        let ell = self.ellipse;
+        let major = ell.major_axis.x.hypot(ell.major_axis.y);
+        let minor = major * ell.minor_axis_ratio;
+
+        //an axis aligned ellipse centered on the origin, rotated and moved into place.
+        //The center and axis are already in world coordinates, but the parameters go
+        //around the extrusion direction, so they run clockwise when it points down
+        let mirror = if ell.normal.z < 0.0 {
+            Transform::scaling(1.0, -1.0)
+        } else {
+            Transform::identity()
+        };
+        let local = mirror
+            .then(&Transform::rotation(
+                ell.major_axis.y.atan2(ell.major_axis.x).to_degrees(),
+            ))
+            .then(&Transform::translation(ell.center.x, ell.center.y))
+            .then(tf);
+
+        let start = ell.start_parameter.to_degrees();
+        let mut sweep = (ell.end_parameter - ell.start_parameter)
+            .to_degrees()
+            .rem_euclid(360.0);
+        let full = sweep < 1e-6 || (sweep - 360.0).abs() < 1e-6;
+        if full {
+            sweep = 360.0;
+        }
+
+        let is_circle = (ell.minor_axis_ratio - 1.0).abs() < 1e-9;
+        if !(local.keeps_axes() || (is_circle && local.is_uniform())) {
+            trace!("Ellipse isn't axis aligned under {local:?}, converting to a polygon");
+            return self.tessellate(&local, major, minor, start, sweep, full);
+        }
+
+        let style = Style::default();
+        if full {
+            Ellipse {
+                x: -major,
+                y: -minor,
+                width: major * 2.0,
+                height: minor * 2.0,
+                antialias: false,
+                style,
+            }
+            .transform(&local)
+        } else {
+            Arc::elliptical(major, minor, start, sweep, style).transform(&local)
+        }
+    }
+
+    fn tessellate(
+        &self,
+        local: &Transform,
+        major: f64,
+        minor: f64,
+        start: f64,
+        sweep: f64,
+        full: bool,
+    ) -> Objects {
+        //the step where the chord strays from the curve by the tolerance, worked
+        //out for the most curved part of the ellipse (well, close enough to it)
+        let radius = major * local.scale_x().max(local.scale_y());
+        let step = if self.tolerance > 0.0 && self.tolerance < radius {
+            (2.0 * (1.0 - self.tolerance / radius).acos()).to_degrees()
+        } else {
+            MAX_TESSELLATE_STEP_DEG
+        }
+        .clamp(MIN_TESSELLATE_STEP_DEG, MAX_TESSELLATE_STEP_DEG);
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let steps = (sweep / step).ceil().max(1.0) as usize;
+        let mut coordinates: Vec<Coordinate> = (0..=steps)
+            .map(|i| {
+                #[allow(clippy::cast_precision_loss)]
+                let (sin, cos) = (start + sweep * i as f64 / steps as f64)
+                    .to_radians()
+                    .sin_cos();
+                let (x, y) = local.apply(major * cos, minor * sin);
+                Coordinate { x, y: -y }
+            })
+            .collect();
+
+        //the last point lands back on the first, which the closed polygon doesn't need
+        if full {
+            coordinates.pop();
         }
+        Objects::Polygon(Polygon::new(coordinates, full, Style::default()))
     }
 }
 
+//keeps the number of points in a tessellated ellipse sane, whatever the tolerance is
+const MIN_TESSELLATE_STEP_DEG: f64 = 0.5;
+const MAX_TESSELLATE_STEP_DEG: f64 = 15.0;
+
 impl TryFrom<&Polyline> for Ellipse {
-    type Error = &'static str; //add better error later
+    type Error = ConversionError;
 
     fn try_from(poly: &Polyline) -> Result<Self, Self::Error> {
         if !poly.is_circular() {
-            return Err("Polyline has poor circularity, can't convert");
+            return Err(ConversionError::NotCircular);
         }
 
         //I did this fold because min requires the vertex to have the Ordering trait
@@ -91,17 +185,17 @@ impl TryFrom<&Polyline> for Ellipse {
             //in the original code antialias is always set to false...I'm guessing for performance
             //reasons...I'm trying to think if there is a time we might want to turn it on?
             antialias: false,
-            style: "line-style:normal;line-weight:thin;filling:none;color:black".into(),
+            style: Style::default(),
         })
     }
 }
 
 impl TryFrom<&LwPolyline> for Ellipse {
-    type Error = &'static str; //add better error later
+    type Error = ConversionError;
 
     fn try_from(poly: &LwPolyline) -> Result<Self, Self::Error> {
         if !poly.is_circular() {
-            return Err("Polyline has poor circularity, can't convert");
+            return Err(ConversionError::NotCircular);
         }
 
         let x = poly
@@ -133,7 +227,7 @@ impl TryFrom<&LwPolyline> for Ellipse {
             //in the original code antialias is always set to false...I'm guessing for performance
             //reasons...I'm trying to think if there is a time we might want to turn it on?
             antialias: false,
-            style: "line-style:normal;line-weight:thin;filling:none;color:black".into(),
+            style: Style::default(),
         })
     }
 }
@@ -151,27 +245,179 @@ impl From<&Ellipse> for XMLElement {
     }
 }
 
-impl ScaleEntity for Ellipse {
-    fn scale(&mut self, fact_x: f64, fact_y: f64) {
-        self.x *= fact_x;
-        self.y *= fact_y;
-        self.width *= fact_x;
-        self.height *= fact_y;
+impl TransformEntity for Ellipse {
+    fn transform(mut self, tf: &Transform) -> Objects {
+        if !(tf.keeps_axes() || (tf.is_uniform() && (self.width - self.height).abs() < EPSILON)) {
+            //A QET ellipse is always axis aligned, so once it's rotated (or a circle
+            //is skewed) the only option left is to tessellate it
+            trace!("Ellipse can't stay axis aligned under {tf:?}, converting to a polygon");
+            let (rx, ry) = (self.width / 2.0, self.height / 2.0);
+            let (cx, cy) = (self.x + rx, self.y + ry);
+            //the last point lands back on the first, which the closed polygon doesn't need
+            let mut points = Transform::tessellate(0.0, 360.0);
+            points.pop();
+            let coordinates = points
+                .into_iter()
+                .map(|(cos, sin)| {
+                    let (x, y) = tf.apply_qet(cx + rx * cos, cy - ry * sin);
+                    Coordinate { x, y }
+                })
+                .collect();
+
+            return Objects::Polygon(Polygon::new(coordinates, true, self.style));
+        }
+
+        let (cx, cy) = tf.apply_qet(self.x + self.width / 2.0, self.y + self.height / 2.0);
+        let (rx, ry) = tf.radii(self.width / 2.0, self.height / 2.0);
+
+        self.x = cx - rx;
+        self.y = cy - ry;
+        self.width = rx * 2.0;
+        self.height = ry * 2.0;
+        Objects::Ellipse(self)
     }
+}
 
-    fn left_bound(&self) -> f64 {
-        self.x
+impl Bounds for Ellipse {
+    fn bounding_box(&self) -> BoundingBox {
+        BoundingBox {
+            left: self.x,
+            top: self.y,
+            right: self.x + self.width,
+            bottom: self.y + self.height,
+        }
     }
+}
 
-    fn right_bound(&self) -> f64 {
-        self.x + self.width
+impl ToSvg for Ellipse {
+    fn to_svg(&self, fills: &mut PatternFills) -> XMLElement {
+        let mut ell_svg = XMLElement::new("ellipse");
+        ell_svg.add_attribute("cx", two_dec(self.x + self.width / 2.0));
+        ell_svg.add_attribute("cy", two_dec(self.y + self.height / 2.0));
+        ell_svg.add_attribute("rx", two_dec(self.width / 2.0));
+        ell_svg.add_attribute("ry", two_dec(self.height / 2.0));
+        add_style(&mut ell_svg, &self.style, fills);
+        ell_svg
+    }
+}
+
+impl Paint for Ellipse {
+    #[allow(clippy::cast_possible_truncation)]
+    fn paint(&self, canvas: &mut Canvas) {
+        let path = tiny_skia::Rect::from_xywh(
+            self.x as f32,
+            self.y as f32,
+            self.width as f32,
+            self.height as f32,
+        )
+        .and_then(tiny_skia::PathBuilder::from_oval);
+        canvas.shape(path, &self.style);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DxfEllipse;
+    use crate::qelmt::{BoundingBox, Bounds, Objects, Transform};
+    use dxf::entities;
+    use dxf::{Point, Vector};
+    use std::f64::consts::{FRAC_PI_2, PI};
+
+    //half as wide as it is long and centered on (10, 5), QET bounds are upside down from the dxf ones
+    fn ellipse(major_axis: Vector, start: f64, end: f64) -> entities::Ellipse {
+        entities::Ellipse {
+            center: Point::new(10.0, 5.0, 0.0),
+            major_axis,
+            minor_axis_ratio: 0.5,
+            start_parameter: start,
+            end_parameter: end,
+            ..Default::default()
+        }
+    }
+
+    fn to_objects(ell: &entities::Ellipse, tolerance: f64) -> Objects {
+        DxfEllipse {
+            ellipse: ell,
+            tolerance,
+        }
+        .to_objects(&Transform::identity())
     }
 
-    fn top_bound(&self) -> f64 {
-        self.y
+    fn close(bbox: &BoundingBox, left: f64, top: f64, right: f64, bottom: f64) -> bool {
+        [
+            (bbox.left, left),
+            (bbox.top, top),
+            (bbox.right, right),
+            (bbox.bottom, bottom),
+        ]
+        .iter()
+        .all(|(a, b)| (a - b).abs() < 1e-9)
     }
 
-    fn bot_bound(&self) -> f64 {
-        self.y + self.height
+    #[test]
+    fn axis_aligned_is_an_ellipse() {
+        let objects = to_objects(&ellipse(Vector::new(2.0, 0.0, 0.0), 0.0, 2.0 * PI), 0.0);
+        assert!(matches!(objects, Objects::Ellipse(_)));
+        assert!(close(&objects.bounding_box(), 8.0, -6.0, 12.0, -4.0));
+    }
+
+    #[test]
+    fn partial_is_an_arc() {
+        //the first quadrant, from the end of the major axis up to the minor one
+        let objects = to_objects(&ellipse(Vector::new(2.0, 0.0, 0.0), 0.0, FRAC_PI_2), 0.0);
+        assert!(matches!(objects, Objects::Arc(_)));
+        assert!(close(&objects.bounding_box(), 10.0, -6.0, 12.0, -5.0));
+
+        //an end before the start sweeps through 0, so this is the right half
+        let objects = to_objects(
+            &ellipse(Vector::new(2.0, 0.0, 0.0), 3.0 * FRAC_PI_2, FRAC_PI_2),
+            0.0,
+        );
+        assert!(matches!(objects, Objects::Arc(_)));
+        assert!(close(&objects.bounding_box(), 10.0, -6.0, 12.0, -4.0));
+    }
+
+    #[test]
+    fn downward_extrusion_runs_clockwise() {
+        //the same quadrant, but going down from the major axis
+        let mut ell = ellipse(Vector::new(2.0, 0.0, 0.0), 0.0, FRAC_PI_2);
+        ell.normal = Vector::new(0.0, 0.0, -1.0);
+        let objects = to_objects(&ell, 0.0);
+        assert!(matches!(objects, Objects::Arc(_)));
+        assert!(close(&objects.bounding_box(), 10.0, -5.0, 12.0, -4.0));
+    }
+
+    #[test]
+    fn rotated_is_a_polygon() {
+        let axis = Vector::new(2.0_f64.sqrt(), 2.0_f64.sqrt(), 0.0);
+        let Objects::Polygon(polygon) = to_objects(&ellipse(axis, 0.0, 2.0 * PI), 0.0) else {
+            panic!("a rotated ellipse should be a polygon");
+        };
+
+        //every point is on the ellipse, once rotated back
+        let (sin, cos) = (-45.0_f64).to_radians().sin_cos();
+        for coord in &polygon.coordinates {
+            let (dx, dy) = (coord.x - 10.0, -coord.y - 5.0);
+            let (x, y) = (dx * cos - dy * sin, dx * sin + dy * cos);
+            assert!(((x / 2.0).powi(2) + y.powi(2) - 1.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn tolerance_sets_the_step_count() {
+        let axis = Vector::new(50.0_f64.sqrt(), 50.0_f64.sqrt(), 0.0);
+        let ell = ellipse(axis, 0.0, 2.0 * PI);
+        let points = |tolerance| match to_objects(&ell, tolerance) {
+            Objects::Polygon(polygon) => polygon.coordinates.len(),
+            _ => panic!("a rotated ellipse should be a polygon"),
+        };
+
+        //no tolerance, or one bigger than the ellipse, uses the biggest step
+        assert_eq!(points(0.0), 24);
+        assert_eq!(points(20.0), 24);
+        //2 * acos(1 - 0.01 / 10) is about 5.13 degrees
+        assert_eq!(points(0.01), 71);
+        //and a tiny one the smallest step
+        assert_eq!(points(1e-9), 720);
     }
 }
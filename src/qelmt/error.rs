@@ -0,0 +1,119 @@
+use dxf::entities::{Entity, EntityCommon, EntityType};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Why something in the drawing couldn't be turned into part of the element
+#[derive(Debug, Clone, PartialEq, Eq, Error, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "detail", rename_all = "snake_case")]
+pub enum ConversionError {
+    #[error("the {0} doesn't have enough points to draw anything")]
+    NotEnoughPoints(String),
+
+    #[error("a polyline with {0} points can't be turned into a line")]
+    NotALine(usize),
+
+    #[error("the polyline isn't close enough to a circle to be one")]
+    NotCircular,
+
+    #[error("the block {0} isn't in the drawing")]
+    BlockNotFound(String),
+
+    #[error("the dimension type isn't supported")]
+    UnsupportedDimension,
+
+    #[error("the hatch doesn't have a boundary that could be read")]
+    NoHatchBoundary,
+
+    #[error("{0} hatches are in a binary dxf file, only the ones in ASCII files can be read")]
+    BinaryHatches(usize),
+
+    #[error("a group turns into several XML elements, not just one")]
+    Group,
+
+    #[error("the entity type isn't supported yet")]
+    Unsupported,
+}
+
+/// A [`ConversionError`] along with the entity it happened on, so it can be tracked down in the drawing
+#[derive(Debug, Clone, PartialEq, Eq, Error, Serialize, Deserialize)]
+#[error("{entity_type} (handle {handle}, layer {layer}): {reason}")]
+pub struct EntityError {
+    pub handle: String,
+    pub layer: String,
+    pub entity_type: String,
+    #[source]
+    pub reason: ConversionError,
+}
+
+impl EntityError {
+    #[must_use]
+    pub fn new(ent: &Entity, reason: ConversionError) -> Self {
+        Self::from_common(&ent.common, entity_type_name(&ent.specific), reason)
+    }
+
+    //for the entities the dxf crate doesn't know about, and reads in some other way
+    pub(crate) fn from_common(
+        common: &EntityCommon,
+        entity_type: impl Into<String>,
+        reason: ConversionError,
+    ) -> Self {
+        Self {
+            handle: format!("{:X}", common.handle.0),
+            layer: common.layer.clone(),
+            entity_type: entity_type.into(),
+            reason,
+        }
+    }
+}
+
+/// The name of the entity type, the same as the variant of [`EntityType`]
+#[must_use]
+pub fn entity_type_name(ent: &EntityType) -> &'static str {
+    match ent {
+        EntityType::Face3D(_) => "Face3D",
+        EntityType::Solid3D(_) => "Solid3D",
+        EntityType::ProxyEntity(_) => "ProxyEntity",
+        EntityType::Arc(_) => "Arc",
+        EntityType::ArcAlignedText(_) => "ArcAlignedText",
+        EntityType::AttributeDefinition(_) => "AttributeDefinition",
+        EntityType::Attribute(_) => "Attribute",
+        EntityType::Body(_) => "Body",
+        EntityType::Circle(_) => "Circle",
+        EntityType::RotatedDimension(_) => "RotatedDimension",
+        EntityType::RadialDimension(_) => "RadialDimension",
+        EntityType::DiameterDimension(_) => "DiameterDimension",
+        EntityType::AngularThreePointDimension(_) => "AngularThreePointDimension",
+        EntityType::OrdinateDimension(_) => "OrdinateDimension",
+        EntityType::Ellipse(_) => "Ellipse",
+        EntityType::Helix(_) => "Helix",
+        EntityType::Image(_) => "Image",
+        EntityType::Insert(_) => "Insert",
+        EntityType::Leader(_) => "Leader",
+        EntityType::Light(_) => "Light",
+        EntityType::Line(_) => "Line",
+        EntityType::LwPolyline(_) => "LwPolyline",
+        EntityType::MLine(_) => "MLine",
+        EntityType::MText(_) => "MText",
+        EntityType::OleFrame(_) => "OleFrame",
+        EntityType::Ole2Frame(_) => "Ole2Frame",
+        EntityType::ModelPoint(_) => "ModelPoint",
+        EntityType::Polyline(_) => "Polyline",
+        EntityType::Ray(_) => "Ray",
+        EntityType::Region(_) => "Region",
+        EntityType::RText(_) => "RText",
+        EntityType::Section(_) => "Section",
+        EntityType::Seqend(_) => "Seqend",
+        EntityType::Shape(_) => "Shape",
+        EntityType::Solid(_) => "Solid",
+        EntityType::Spline(_) => "Spline",
+        EntityType::Text(_) => "Text",
+        EntityType::Tolerance(_) => "Tolerance",
+        EntityType::Trace(_) => "Trace",
+        EntityType::DgnUnderlay(_) => "DgnUnderlay",
+        EntityType::DwfUnderlay(_) => "DwfUnderlay",
+        EntityType::PdfUnderlay(_) => "PdfUnderlay",
+        EntityType::Vertex(_) => "Vertex",
+        EntityType::Wipeout(_) => "Wipeout",
+        EntityType::XLine(_) => "XLine",
+    }
+}
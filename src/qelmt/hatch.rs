@@ -0,0 +1,633 @@
+use super::error::{ConversionError, EntityError};
+use super::polygon::{Bulge, Coordinate, Point, Polygon};
+use super::report::{Outcome, Report};
+use super::style::{ByBlock, LineWeight, Style, StyleResolver};
+use super::{Objects, Transform, TransformEntity};
+use dxf::entities::{EntityCommon, Spline};
+use dxf::{Color, Handle, Vector};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use tracing::warn;
+
+//The dxf crate doesn't know about HATCH entities at all and just drops them while reading,
+//so they get pulled out of the raw group codes of the file here instead.
+//Only ASCII dxf files are handled, the hatches of binary ones are counted so the report
+//can say they're missing, but they aren't drawn.
+
+//the angle between the points when tessellating the curved edges of a boundary
+const HATCH_STEP_DEG: f64 = 5.0;
+
+//how far off (in degrees) the lines of a hatch pattern can be from one of
+//the QET fill patterns and still be drawn with it
+const PATTERN_ANGLE_TOLERANCE: f64 = 5.0;
+
+/// All the hatches in a drawing, grouped by the block they're in
+#[derive(Debug, Default)]
+pub struct Hatches {
+    //the key is the name of the block, the hatches in the entities section are under ""
+    by_block: HashMap<String, Vec<Hatch>>,
+
+    //how many hatches are in a binary file, which can't be read
+    unread: usize,
+}
+
+impl Hatches {
+    /// Pulls the hatches out of the raw contents of a dxf file
+    #[must_use]
+    pub fn parse(bytes: &[u8]) -> Self {
+        let text = String::from_utf8_lossy(bytes);
+        if text.starts_with("AutoCAD Binary DXF") {
+            //the entity type is a null terminated string right after the 0 group code
+            let unread = bytes
+                .windows(b"\0HATCH\0".len())
+                .filter(|win| *win == b"\0HATCH\0")
+                .count();
+            if unread > 0 {
+                warn!("{unread} hatches in a binary dxf file can't be read, they're left out");
+            }
+            return Self {
+                unread,
+                ..Self::default()
+            };
+        }
+
+        let pairs = code_pairs(&text);
+        let mut by_block: HashMap<String, Vec<Hatch>> = HashMap::new();
+        let mut section = "";
+        let mut block = String::new();
+        let mut i = 0;
+        while i < pairs.len() {
+            let (code, value) = pairs[i];
+            i += 1;
+            if code != 0 {
+                continue;
+            }
+
+            //everything up to the next 0 group belongs to this entity
+            let end = pairs[i..]
+                .iter()
+                .position(|(code, _)| *code == 0)
+                .map_or(pairs.len(), |pos| i + pos);
+            let ent = &pairs[i..end];
+
+            match value {
+                "SECTION" => {
+                    section = ent
+                        .iter()
+                        .find_map(|(code, val)| (*code == 2).then_some(*val))
+                        .unwrap_or_default();
+                }
+                "BLOCK" if section == "BLOCKS" => {
+                    block = ent
+                        .iter()
+                        .find_map(|(code, val)| (*code == 2).then_some(*val))
+                        .unwrap_or_default()
+                        .to_string();
+                }
+                "HATCH" if section == "ENTITIES" => {
+                    by_block
+                        .entry(String::new())
+                        .or_default()
+                        .push(Hatch::from(ent));
+                }
+                "HATCH" if section == "BLOCKS" => {
+                    by_block
+                        .entry(block.clone())
+                        .or_default()
+                        .push(Hatch::from(ent));
+                }
+                _ => {}
+            }
+            i = end;
+        }
+
+        Self {
+            by_block,
+            unread: 0,
+        }
+    }
+
+    /// The number of hatches in the entities section, not counting the ones in blocks
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.by_block.get("").map_or(0, Vec::len)
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The number of hatches (in blocks or not) that were left out, because they're
+    /// in a binary dxf file
+    #[must_use]
+    pub fn unread(&self) -> usize {
+        self.unread
+    }
+
+    pub(crate) fn in_entities(&self) -> &[Hatch] {
+        self.in_block("")
+    }
+
+    pub(crate) fn in_block(&self, name: &str) -> &[Hatch] {
+        self.by_block.get(name).map_or(&[], Vec::as_slice)
+    }
+}
+
+fn code_pairs(text: &str) -> Vec<(i32, &str)> {
+    let mut lines = text.lines();
+    let mut pairs = Vec::new();
+    while let (Some(code), Some(value)) = (lines.next(), lines.next()) {
+        let Ok(code) = code.trim().parse() else {
+            break;
+        };
+        pairs.push((code, value.trim()));
+    }
+    pairs
+}
+
+//Steps through the group codes of a single hatch. The boundary data is in a fixed
+//order, but a lot of it is optional, so a value is only taken if the code matches
+struct Reader<'a> {
+    pairs: &'a [(i32, &'a str)],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn peek(&self) -> Option<i32> {
+        self.pairs.get(self.pos).map(|(code, _)| *code)
+    }
+
+    fn take(&mut self, code: i32) -> Option<&'a str> {
+        let (cd, val) = self.pairs.get(self.pos)?;
+        if *cd == code {
+            self.pos += 1;
+            Some(val)
+        } else {
+            None
+        }
+    }
+
+    fn f64(&mut self, code: i32) -> f64 {
+        self.take(code)
+            .and_then(|val| val.parse().ok())
+            .unwrap_or(0.0)
+    }
+
+    fn int(&mut self, code: i32) -> i64 {
+        self.take(code)
+            .and_then(|val| val.parse().ok())
+            .unwrap_or(0)
+    }
+
+    fn count(&mut self, code: i32) -> usize {
+        usize::try_from(self.int(code)).unwrap_or(0)
+    }
+
+    fn point(&mut self, x_code: i32) -> Point {
+        Point::new(self.f64(x_code), self.f64(x_code + 10))
+    }
+
+    fn done(&self) -> bool {
+        self.pos >= self.pairs.len()
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Hatch {
+    pub common: EntityCommon,
+    solid: bool,
+
+    //the boundaries are in the OCS of the hatch
+    ocs: Transform,
+
+    //the boundary loops, in dxf coordinates
+    loops: Vec<Vec<Point>>,
+
+    //the angles of the lines making up the pattern, these already include the angle of the hatch
+    pattern_angles: Vec<f64>,
+}
+
+impl From<&[(i32, &str)]> for Hatch {
+    fn from(pairs: &[(i32, &str)]) -> Self {
+        let mut rdr = Reader { pairs, pos: 0 };
+        let mut common = EntityCommon::default();
+        let mut solid = false;
+        let mut normal = Vector::z_axis();
+        let mut elevation = 0.0;
+
+        //the common entity properties, up to the start of the boundary paths
+        while let Some(code) = rdr.peek() {
+            if code == 91 {
+                break;
+            }
+            let val = pairs[rdr.pos].1;
+            rdr.pos += 1;
+            match code {
+                5 => common.handle = Handle(u64::from_str_radix(val, 16).unwrap_or_default()),
+                8 => common.layer = val.to_string(),
+                6 => common.line_type_name = val.to_string(),
+                62 => {
+                    common.color = match val.parse::<i16>().unwrap_or(256) {
+                        0 => Color::by_block(),
+                        idx @ 1..=255 => Color::from_index(u8::try_from(idx).unwrap_or(7)),
+                        _ => Color::by_layer(),
+                    };
+                }
+                420 => common.color_24_bit = val.parse().unwrap_or_default(),
+                370 => common.lineweight_enum_value = val.parse().unwrap_or(-1),
+                30 => elevation = val.parse().unwrap_or_default(),
+                210 => normal.x = val.parse().unwrap_or_default(),
+                220 => normal.y = val.parse().unwrap_or_default(),
+                230 => normal.z = val.parse().unwrap_or(1.0),
+                2 => solid |= val.eq_ignore_ascii_case("SOLID"),
+                70 => solid |= val == "1",
+                _ => {}
+            }
+        }
+
+        let loop_count = rdr.count(91);
+        let loops = (0..loop_count)
+            .map(|_| read_loop(&mut rdr))
+            .filter(|lp| lp.len() > 2)
+            .collect();
+
+        //the pattern definition lines are whatever is left
+        let mut pattern_angles = Vec::new();
+        while !rdr.done() {
+            match rdr.peek() {
+                Some(53) => pattern_angles.push(rdr.f64(53)),
+                _ => rdr.pos += 1,
+            }
+        }
+
+        Self {
+            common,
+            solid,
+            ocs: Transform::ocs(&normal, elevation),
+            loops,
+            pattern_angles,
+        }
+    }
+}
+
+fn read_loop(rdr: &mut Reader) -> Vec<Point> {
+    let flags = rdr.int(92);
+    let mut points = Vec::new();
+
+    if flags & 2 != 0 {
+        //polyline boundary
+        let has_bulge = rdr.int(72) != 0;
+        let _closed = rdr.int(73);
+        let vertices: Vec<(Point, f64)> = (0..rdr.count(93))
+            .map(|_| {
+                let pt = rdr.point(10);
+                let bulge = if has_bulge { rdr.f64(42) } else { 0.0 };
+                (pt, bulge)
+            })
+            .collect();
+
+        //a boundary is always closed, so the last vertex bulges back to the first
+        for (i, (pt, bulge)) in vertices.iter().enumerate() {
+            points.push(*pt);
+            if *bulge != 0.0 {
+                let next = vertices[(i + 1) % vertices.len()].0;
+                points.extend(
+                    Bulge {
+                        start: *pt,
+                        end: next,
+                        bulge: *bulge,
+                    }
+                    .inner_points(),
+                );
+            }
+        }
+    } else {
+        for _ in 0..rdr.count(93) {
+            let edge = match rdr.int(72) {
+                1 => {
+                    let start = rdr.point(10);
+                    vec![start, rdr.point(11)]
+                }
+                2 => {
+                    let center = rdr.point(10);
+                    let radius = rdr.f64(40);
+                    let (start, end, ccw) = (rdr.f64(50), rdr.f64(51), rdr.int(73) != 0);
+                    arc_points(start, end, ccw, |ang| {
+                        Point::new(center.x + radius * ang.cos(), center.y + radius * ang.sin())
+                    })
+                }
+                3 => {
+                    let center = rdr.point(10);
+                    let major = rdr.point(11);
+                    let ratio = rdr.f64(40);
+                    let (start, end, ccw) = (rdr.f64(50), rdr.f64(51), rdr.int(73) != 0);
+                    arc_points(start, end, ccw, |ang| {
+                        let (sin, cos) = ang.sin_cos();
+                        Point::new(
+                            center.x + major.x * cos - major.y * ratio * sin,
+                            center.y + major.y * cos + major.x * ratio * sin,
+                        )
+                    })
+                }
+                4 => read_spline_edge(rdr),
+                _ => Vec::new(),
+            };
+
+            //the edges connect end to start, no need for the point twice
+            let mut edge = edge.into_iter().peekable();
+            if let (Some(last), Some(first)) = (points.last(), edge.peek()) {
+                if same_point(*last, *first) {
+                    edge.next();
+                }
+            }
+            points.extend(edge);
+        }
+    }
+
+    //the handles of the entities the boundary was made from
+    for _ in 0..rdr.count(97) {
+        rdr.take(330);
+    }
+
+    //the polygon is closed anyways
+    if let (Some(first), Some(last)) = (points.first(), points.last()) {
+        if points.len() > 1 && same_point(*first, *last) {
+            points.pop();
+        }
+    }
+    points
+}
+
+fn same_point(a: Point, b: Point) -> bool {
+    (a.x - b.x).hypot(a.y - b.y) < 1e-9
+}
+
+//The angles of an arc edge in a boundary are always stored counter clockwise, for a
+//clockwise edge they're mirrored, so flip them back to walk the edge in its own direction
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::cast_precision_loss
+)]
+fn arc_points(start: f64, end: f64, ccw: bool, point_at: impl Fn(f64) -> Point) -> Vec<Point> {
+    let (start, end) = if ccw { (start, end) } else { (-start, -end) };
+    let mut sweep = (end - start).rem_euclid(360.0);
+    if sweep < 1e-9 {
+        sweep = 360.0;
+    }
+    if !ccw {
+        sweep = -(360.0 - sweep).rem_euclid(360.0);
+        if sweep.abs() < 1e-9 {
+            sweep = -360.0;
+        }
+    }
+
+    let steps = (sweep.abs() / HATCH_STEP_DEG).ceil().max(1.0) as usize;
+    (0..=steps)
+        .map(|i| point_at((start + sweep * i as f64 / steps as f64).to_radians()))
+        .collect()
+}
+
+fn read_spline_edge(rdr: &mut Reader) -> Vec<Point> {
+    let degree = rdr.int(94);
+    let rational = rdr.int(73) != 0;
+    let _periodic = rdr.int(74);
+    let knot_count = rdr.count(95);
+    let ctrl_count = rdr.count(96);
+    let knots: Vec<f64> = (0..knot_count).map(|_| rdr.f64(40)).collect();
+    let control_points: Vec<dxf::Point> = (0..ctrl_count)
+        .map(|_| {
+            let pt = rdr.point(10);
+            if rational {
+                rdr.take(42);
+            }
+            dxf::Point::new(pt.x, pt.y, 0.0)
+        })
+        .collect();
+
+    //fit data was only added in 2010, so it might not be there at all
+    let fit_points: Vec<Point> = match rdr.peek() {
+        Some(97) => (0..rdr.count(97)).map(|_| rdr.point(11)).collect(),
+        _ => Vec::new(),
+    };
+    rdr.take(12);
+    rdr.take(22);
+    rdr.take(13);
+    rdr.take(23);
+
+    let Ok(degree) = i32::try_from(degree) else {
+        return fit_points;
+    };
+    if degree < 1 || knots.len() != control_points.len() + degree.unsigned_abs() as usize + 1 {
+        return fit_points;
+    }
+
+    let spline = Spline {
+        degree_of_curve: degree,
+        knot_values: knots,
+        control_points,
+        ..Default::default()
+    };
+    let mut points: Vec<Point> = Polygon::from((&spline, 20))
+        .coordinates
+        .iter()
+        .map(|coord| Point::new(coord.x, -coord.y))
+        .collect();
+
+    //the tessellation stops short of the end of the knot domain
+    if let Some(last) = spline.control_points.last() {
+        points.push(Point::new(last.x, last.y));
+    }
+    points
+}
+
+impl Hatch {
+    pub fn build(
+        &self,
+        transform: &Transform,
+        styles: Option<&StyleResolver>,
+        by_block: &ByBlock,
+    ) -> Result<Objects, EntityError> {
+        if self.loops.is_empty() {
+            return Err(EntityError::from_common(
+                &self.common,
+                "Hatch",
+                ConversionError::NoHatchBoundary,
+            ));
+        }
+        let transform = &self.ocs.then(transform);
+
+        let color = styles.map_or("black", |styles| styles.color_name(&self.common, by_block));
+        let filling = if self.solid {
+            Some(color)
+        } else {
+            self.pattern_filling(transform)
+        };
+
+        //QET polygons can't have holes, so loops nested in an odd number of others are
+        //drawn on top in white to cut them out again
+        let mut loops: Vec<(usize, &Vec<Point>)> = self
+            .loops
+            .iter()
+            .map(|lp| {
+                let depth = self
+                    .loops
+                    .iter()
+                    .filter(|other| !std::ptr::eq(*other, lp) && contains(other, lp[0]))
+                    .count();
+                (depth, lp)
+            })
+            .collect();
+        loops.sort_by_key(|(depth, _)| *depth);
+
+        Ok(Objects::Group(
+            loops
+                .into_iter()
+                .map(|(depth, lp)| {
+                    //when the pattern doesn't match up with anything in QET at least draw the outline
+                    let style = match filling {
+                        Some(filling) => Style {
+                            line_weight: LineWeight::None,
+                            filling: if depth % 2 == 0 { filling } else { "white" }.into(),
+                            color: color.into(),
+                            ..Default::default()
+                        },
+                        None => Style {
+                            color: color.into(),
+                            ..Default::default()
+                        },
+                    };
+                    let coords = lp
+                        .iter()
+                        .map(|pt| Coordinate { x: pt.x, y: -pt.y })
+                        .collect();
+                    Polygon::new(coords, true, style).transform(transform)
+                })
+                .collect(),
+        ))
+    }
+
+    //builds the hatch, and writes down what came of it in the report
+    pub fn build_reported(
+        &self,
+        transform: &Transform,
+        styles: Option<&StyleResolver>,
+        by_block: &ByBlock,
+        report: Option<&RefCell<Report>>,
+        block_path: &[String],
+    ) -> Option<Objects> {
+        let res = self.build(transform, styles, by_block);
+        let outcome = match &res {
+            Ok(obj) if !self.solid && self.pattern_filling(&self.ocs.then(transform)).is_none() => {
+                Outcome::Approximated {
+                    parts: Outcome::parts(obj),
+                    reason:
+                        "QET doesn't have a filling for the pattern, so only the outline is drawn"
+                            .into(),
+                }
+            }
+            Ok(obj) => Outcome::Converted {
+                parts: Outcome::parts(obj),
+            },
+            Err(err) => {
+                warn!("{err}");
+                Outcome::dropped(&err.reason)
+            }
+        };
+        if let Some(report) = report {
+            report
+                .borrow_mut()
+                .add(&self.common, "Hatch", block_path, outcome);
+        }
+        res.ok()
+    }
+
+    //The QET fill patterns are just lines in one direction, so this only works out when all
+    //the lines in the hatch pattern go in a direction that matches up with one of them
+    fn pattern_filling(&self, transform: &Transform) -> Option<&'static str> {
+        let mut fillings = self.pattern_angles.iter().map(|angle| {
+            //the pattern gets turned and mirrored along with the block it's in
+            let (sin, cos) = angle.to_radians().sin_cos();
+            let (x, y) = transform.apply_vector(cos, sin);
+            let angle = y.atan2(x).to_degrees().rem_euclid(180.0);
+            [
+                (0.0, "hor"),
+                (45.0, "bdiag"),
+                (90.0, "ver"),
+                (135.0, "fdiag"),
+                (180.0, "hor"),
+            ]
+            .iter()
+            .find(|(fill_angle, _)| (angle - fill_angle).abs() <= PATTERN_ANGLE_TOLERANCE)
+            .map(|(_, fill)| *fill)
+        });
+
+        let first = fillings.next()??;
+        fillings.all(|fill| fill == Some(first)).then_some(first)
+    }
+}
+
+//even-odd test for whether the point is inside the loop
+fn contains(lp: &[Point], pt: Point) -> bool {
+    let mut inside = false;
+    let mut prev = lp[lp.len() - 1];
+    for cur in lp {
+        if (cur.y > pt.y) != (prev.y > pt.y)
+            && pt.x < (prev.x - cur.x) * (pt.y - cur.y) / (prev.y - cur.y) + cur.x
+        {
+            inside = !inside;
+        }
+        prev = *cur;
+    }
+    inside
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{code_pairs, Hatches};
+
+    //a solid square in the entities section, and a line edge triangle in a block
+    const SAMPLE: &str = "  0\nSECTION\n  2\nBLOCKS\n  0\nBLOCK\n  2\nTRI\n  0\nHATCH\n  5\n2B\n  8\n0\n  2\nANSI31\n 70\n0\n 91\n1\n 92\n1\n 93\n3\n 72\n1\n 10\n0.0\n 20\n0.0\n 11\n1.0\n 21\n0.0\n 72\n1\n 10\n1.0\n 20\n0.0\n 11\n0.0\n 21\n1.0\n 72\n1\n 10\n0.0\n 20\n1.0\n 11\n0.0\n 21\n0.0\n 53\n45.0\n  0\nENDBLK\n  0\nENDSEC\n  0\nSECTION\n  2\nENTITIES\n  0\nHATCH\n  5\n2A\n  8\nFILL\n  2\nSOLID\n 70\n1\n 91\n1\n 92\n2\n 72\n0\n 73\n1\n 93\n4\n 10\n0.0\n 20\n0.0\n 10\n2.0\n 20\n0.0\n 10\n2.0\n 20\n2.0\n 10\n0.0\n 20\n2.0\n  0\nENDSEC\n  0\nEOF\n";
+
+    #[test]
+    fn code_pairs_trims_codes_and_values() {
+        let pairs = code_pairs("  0\nHATCH\n 10\n 1.5 \n 20\r\n2.0\r\n");
+        assert_eq!(pairs, [(0, "HATCH"), (10, "1.5"), (20, "2.0")]);
+    }
+
+    #[test]
+    fn code_pairs_stops_at_a_bad_code() {
+        let pairs = code_pairs("  0\nHATCH\nnot a code\nvalue\n 10\n1.0\n");
+        assert_eq!(pairs, [(0, "HATCH")]);
+    }
+
+    #[test]
+    fn parse_reads_the_hatches_of_the_entities_and_blocks() {
+        let hatches = Hatches::parse(SAMPLE.as_bytes());
+        assert_eq!(hatches.len(), 1);
+        assert_eq!(hatches.unread(), 0);
+
+        let square = &hatches.in_entities()[0];
+        assert_eq!(square.common.handle.0, 0x2A);
+        assert_eq!(square.common.layer, "FILL");
+        assert!(square.solid);
+        assert_eq!(square.loops.len(), 1);
+        assert_eq!(square.loops[0].len(), 4);
+        assert!((square.loops[0][2].x - 2.0).abs() < 1e-9);
+
+        let tri = &hatches.in_block("TRI")[0];
+        assert!(!tri.solid);
+        assert_eq!(tri.loops.len(), 1);
+        assert_eq!(tri.pattern_angles, [45.0]);
+    }
+
+    #[test]
+    fn parse_counts_the_hatches_of_a_binary_file() {
+        let mut bytes = b"AutoCAD Binary DXF\r\n\x1a\0".to_vec();
+        bytes.extend_from_slice(b"\0\0HATCH\0\x05\0\x2A\0\0\0HATCH\0\0\0EOF\0");
+        let hatches = Hatches::parse(&bytes);
+        assert!(hatches.is_empty());
+        assert_eq!(hatches.unread(), 2);
+    }
+}
@@ -0,0 +1,283 @@
+use super::{wildcard_match, ElemInfo, ElemInfos};
+use dxf::entities::EntityType;
+use dxf::objects::ObjectType;
+use dxf::Drawing;
+use std::collections::{BTreeMap, HashMap};
+
+/// The element informations QET knows about, the names the element editor lists
+pub const ELEMENT_INFO_KEYS: [&str; 11] = [
+    "label",
+    "comment",
+    "description",
+    "designation",
+    "manufacturer",
+    "manufacturer_reference",
+    "machine_manufacturer_reference",
+    "supplier",
+    "quantity",
+    "unity",
+    "plant",
+];
+
+//the names vendors tend to use for the attributes, and the drawing properties,
+//that hold each of the element informations
+const DEFAULT_INFO_SOURCES: [(&str, &str); 29] = [
+    ("LABEL", "label"),
+    ("COMMENT", "comment"),
+    ("COMMENTS", "comment"),
+    ("DESCRIPTION", "description"),
+    ("DESC", "description"),
+    ("SUBJECT", "description"),
+    ("DESIGNATION", "designation"),
+    ("TITLE", "designation"),
+    ("MANUFACTURER", "manufacturer"),
+    ("MFR", "manufacturer"),
+    ("MAKER", "manufacturer"),
+    ("BRAND", "manufacturer"),
+    ("MANUFACTURER_REFERENCE", "manufacturer_reference"),
+    ("PART_NUMBER", "manufacturer_reference"),
+    ("PARTNUMBER", "manufacturer_reference"),
+    ("PART_NO", "manufacturer_reference"),
+    ("PARTNO", "manufacturer_reference"),
+    ("PN", "manufacturer_reference"),
+    ("MPN", "manufacturer_reference"),
+    ("ORDER_NUMBER", "manufacturer_reference"),
+    (
+        "MACHINE_MANUFACTURER_REFERENCE",
+        "machine_manufacturer_reference",
+    ),
+    ("SUPPLIER", "supplier"),
+    ("VENDOR", "supplier"),
+    ("DISTRIBUTOR", "supplier"),
+    ("QUANTITY", "quantity"),
+    ("QTY", "quantity"),
+    ("UNITY", "unity"),
+    ("UNIT", "unity"),
+    ("PLANT", "plant"),
+];
+
+/// Attribute tag or drawing property -> QET element information, what
+/// [`crate::ConversionOptions::element_info`] starts out as. The names can have
+/// `*` in them to match anything
+#[must_use]
+pub fn default_info_map() -> HashMap<String, String> {
+    DEFAULT_INFO_SOURCES
+        .iter()
+        .map(|(source, key)| ((*source).to_string(), (*key).to_string()))
+        .collect()
+}
+
+/// The element information an attribute tag or drawing property goes into, the names
+/// spelled out in full win over the ones with a `*`. None for an empty element information
+pub(crate) fn info_for<'m>(info_map: &'m HashMap<String, String>, name: &str) -> Option<&'m str> {
+    //sorted so it's always the same one when several of them match
+    info_map
+        .iter()
+        .filter(|(source, _)| source.eq_ignore_ascii_case(name))
+        .min_by_key(|(source, _)| source.as_str())
+        .or_else(|| {
+            info_map
+                .iter()
+                .filter(|(source, _)| source.contains('*') && wildcard_match(source, name))
+                .min_by_key(|(source, _)| source.as_str())
+        })
+        .map(|(_, key)| key.as_str())
+        .filter(|key| !key.is_empty())
+}
+
+/// The element information a placeholder text stands in for, either %NAME% with NAME
+/// looked up like an attribute tag, or the whole text when it ends in ?. Short of a
+/// match for it, a text like -K? is the usual placeholder for the label
+pub(crate) fn placeholder_info<'m>(
+    info_map: &'m HashMap<String, String>,
+    text: &str,
+) -> Option<&'m str> {
+    let text = text.trim();
+    if let Some(name) = text
+        .strip_prefix('%')
+        .and_then(|text| text.strip_suffix('%'))
+    {
+        return info_for(info_map, name);
+    }
+    if text.ends_with('?') {
+        return info_for(info_map, text)
+            .or_else(|| (text.len() > 2 && text.starts_with('-')).then_some("label"));
+    }
+    None
+}
+
+//the xrecord AutoCAD keeps the drawing properties (File > Drawing Properties) in
+const DWGPROPS_COOKIE: &str = "DWGPROPS COOKIE";
+
+//the drawing properties by name: the standard ones, the custom ones and the project name from the header
+fn drawing_properties(drw: &Drawing) -> Vec<(String, String)> {
+    let mut props = vec![("PROJECTNAME".to_string(), drw.header.project_name.clone())];
+
+    let dwgprops = drw.objects().find_map(|obj| match &obj.specific {
+        ObjectType::XRecordObject(xrec)
+            if xrec.data_pairs.iter().any(|pair| {
+                pair.code == 1
+                    && pair
+                        .assert_string()
+                        .is_ok_and(|cookie| cookie == DWGPROPS_COOKIE)
+            }) =>
+        {
+            Some(xrec)
+        }
+        _ => None,
+    });
+    for pair in dwgprops.iter().flat_map(|xrec| &xrec.data_pairs) {
+        let Ok(value) = pair.assert_string() else {
+            continue;
+        };
+        let name = match pair.code {
+            2 => "TITLE",
+            3 => "SUBJECT",
+            4 => "AUTHOR",
+            6 => "COMMENTS",
+            7 => "KEYWORDS",
+            8 => "LASTSAVEDBY",
+            9 => "REVISION",
+            //the custom properties are written as NAME=VALUE
+            300..=309 => {
+                if let Some((name, value)) = value.split_once('=') {
+                    props.push((name.trim().to_string(), value.trim().to_string()));
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        props.push((name.to_string(), value));
+    }
+    props
+}
+
+/// The tags and values of the attribute definitions in the drawing, followed by the
+/// attributes of its inserts. Only the ones in the drawing itself, not inside of blocks
+pub(crate) fn drawing_attributes(drw: &Drawing) -> Vec<(String, String)> {
+    let attdefs = drw.entities().filter_map(|ent| match &ent.specific {
+        EntityType::AttributeDefinition(attdef) => {
+            Some((attdef.text_tag.clone(), attdef.value.clone()))
+        }
+        _ => None,
+    });
+    let attribs = drw.entities().flat_map(|ent| match &ent.specific {
+        EntityType::Insert(ins) => ins
+            .attributes()
+            .map(|att| (att.attribute_tag.clone(), att.value.clone()))
+            .collect(),
+        _ => Vec::new(),
+    });
+    attdefs.chain(attribs).collect()
+}
+
+/// The element informations filled in from the drawing properties, the attribute definitions
+/// and the attributes of the inserts in the drawing, in that order so the attributes win.
+/// None when nothing in the drawing maps onto one
+pub(crate) fn element_infos(
+    drw: &Drawing,
+    info_map: &HashMap<String, String>,
+) -> Option<ElemInfos> {
+    let mut infos = BTreeMap::new();
+    for (source, value) in drawing_properties(drw)
+        .into_iter()
+        .chain(drawing_attributes(drw))
+    {
+        let value = value.trim();
+        if value.is_empty() {
+            continue;
+        }
+        //an empty key leaves the source out, for turning off one of the defaults
+        if let Some(key) = info_for(info_map, &source) {
+            infos.insert(key.to_string(), value.to_string());
+        }
+    }
+
+    if infos.is_empty() {
+        return None;
+    }
+    Some(ElemInfos {
+        elem_info: infos
+            .into_iter()
+            .map(|(name, value)| ElemInfo {
+                name,
+                show: 1,
+                value,
+            })
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{default_info_map, element_infos, info_for, placeholder_info};
+    use dxf::entities::{AttributeDefinition, Entity, EntityType};
+    use dxf::Drawing;
+    use std::collections::HashMap;
+
+    fn info_map(sources: &[(&str, &str)]) -> HashMap<String, String> {
+        sources
+            .iter()
+            .map(|(source, key)| ((*source).to_string(), (*key).to_string()))
+            .collect()
+    }
+
+    fn drawing(attdefs: &[(&str, &str)]) -> Drawing {
+        let mut drw = Drawing::new();
+        for (tag, value) in attdefs {
+            drw.add_entity(Entity::new(EntityType::AttributeDefinition(
+                AttributeDefinition {
+                    text_tag: (*tag).to_string(),
+                    value: (*value).to_string(),
+                    ..Default::default()
+                },
+            )));
+        }
+        drw
+    }
+
+    #[test]
+    fn exact_name_beats_wildcard() {
+        let map = info_map(&[("PART*", "comment"), ("PART_NO", "manufacturer_reference")]);
+        assert_eq!(info_for(&map, "part_no"), Some("manufacturer_reference"));
+        assert_eq!(info_for(&map, "PART_NAME"), Some("comment"));
+        assert_eq!(info_for(&map, "NAME"), None);
+    }
+
+    #[test]
+    fn empty_info_disables_a_default() {
+        let mut map = default_info_map();
+        assert_eq!(info_for(&map, "PN"), Some("manufacturer_reference"));
+        map.insert("PN".into(), String::new());
+        assert_eq!(info_for(&map, "PN"), None);
+    }
+
+    #[test]
+    fn placeholders() {
+        let mut map = default_info_map();
+        map.insert("=A1?".into(), "plant".into());
+
+        assert_eq!(placeholder_info(&map, "-K?"), Some("label"));
+        assert_eq!(placeholder_info(&map, " %LABEL% "), Some("label"));
+        assert_eq!(placeholder_info(&map, "%UNKNOWN%"), None);
+        assert_eq!(placeholder_info(&map, "=A1?"), Some("plant"));
+        //only a text that's more than the dash is the label
+        assert_eq!(placeholder_info(&map, "-?"), None);
+        assert_eq!(placeholder_info(&map, "-K1"), None);
+    }
+
+    #[test]
+    fn element_infos_from_the_attributes() {
+        let mut map = default_info_map();
+        map.insert("MFR".into(), String::new());
+        let drw = drawing(&[("PN", " 3RT2015 "), ("MFR", "Siemens"), ("QTY", "")]);
+
+        //the empty value and the disabled tag are left out
+        let infos = element_infos(&drw, &map).expect("PN maps onto an information");
+        assert_eq!(infos.elem_info.len(), 1);
+        assert_eq!(infos.elem_info[0].name, "manufacturer_reference");
+        assert_eq!(infos.elem_info[0].value, "3RT2015");
+
+        assert!(element_infos(&drawing(&[("OTHER", "value")]), &map).is_none());
+    }
+}
@@ -0,0 +1,222 @@
+use super::info::drawing_attributes;
+use super::strip_tag_prefix;
+use crate::ConversionOptions;
+use dxf::Drawing;
+use simple_xml_builder::XMLElement;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// How the element is tied to others in the diagram, a coil (master) to its contacts (slaves),
+/// a report to the one on the next folio...
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LinkType {
+    #[default]
+    Simple,
+    Master,
+    Slave,
+    NextReport,
+    PrevReport,
+    TermBlock,
+    Thumbnail,
+}
+
+impl Display for LinkType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Simple => "simple",
+                Self::Master => "master",
+                Self::Slave => "slave",
+                Self::NextReport => "next_report",
+                Self::PrevReport => "previous_report",
+                Self::TermBlock => "terminal",
+                Self::Thumbnail => "thumbnail",
+            }
+        )
+    }
+}
+
+impl FromStr for LinkType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "simple" => Ok(Self::Simple),
+            "master" => Ok(Self::Master),
+            "slave" => Ok(Self::Slave),
+            "next_report" => Ok(Self::NextReport),
+            "previous_report" => Ok(Self::PrevReport),
+            "terminal" => Ok(Self::TermBlock),
+            "thumbnail" => Ok(Self::Thumbnail),
+            _ => Err(format!(
+                "unknown link type '{s}', expected one of simple, master, slave, \
+                 next_report, previous_report, terminal or thumbnail"
+            )),
+        }
+    }
+}
+
+impl LinkType {
+    //what the element editor fills in for each link type, for the ones that aren't given
+    fn default_kind_infos(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Self::Master => &[("type", "coil")],
+            Self::Slave => &[("type", "simple"), ("state", "NO"), ("number", "1")],
+            Self::TermBlock => &[
+                ("type", "generic"),
+                ("function", "generic"),
+                ("led", "false"),
+            ],
+            Self::Simple | Self::NextReport | Self::PrevReport | Self::Thumbnail => &[],
+        }
+    }
+}
+
+/// The attribute tag that sets the link type of the element from the drawing
+pub const LINK_TYPE_TAG: &str = "LINK_TYPE";
+
+/// The attribute tags starting with this set a kind information of the element,
+/// `KIND_STATE` for the state of a contact for example
+pub const KIND_TAG_PREFIX: &str = "KIND_";
+
+/// Whether the attribute is only there to set the link type or a kind information,
+/// and not something to draw
+pub(crate) fn is_kind_tag(tag: &str) -> bool {
+    tag.eq_ignore_ascii_case(LINK_TYPE_TAG) || kind_name(tag).is_some()
+}
+
+//the name of the kind information a KIND_* tag sets
+fn kind_name(tag: &str) -> Option<&str> {
+    strip_tag_prefix(tag, KIND_TAG_PREFIX)
+}
+
+/// The kind informations of a master, slave or terminal element, by name
+#[derive(Debug)]
+pub struct KindInfos(BTreeMap<String, String>);
+
+impl From<&KindInfos> for XMLElement {
+    fn from(kinds: &KindInfos) -> Self {
+        let mut list_xml = XMLElement::new("kindInformations");
+        for (name, value) in &kinds.0 {
+            let mut info_xml = XMLElement::new("kindInformation");
+            info_xml.add_attribute("name", name);
+            info_xml.add_attribute("show", 1);
+            info_xml.add_text(value);
+            list_xml.add_child(info_xml);
+        }
+        list_xml
+    }
+}
+
+/// The link type and the kind informations of the element, from the options if they're
+/// set there, otherwise from the `LINK_TYPE` and `KIND_*` attributes in the drawing
+pub(crate) fn element_kind(
+    drw: &Drawing,
+    options: &ConversionOptions,
+) -> (LinkType, Option<KindInfos>) {
+    let attributes = drawing_attributes(drw);
+
+    let link_type = options.link_type.unwrap_or_else(|| {
+        attributes
+            .iter()
+            .filter(|(tag, _)| tag.eq_ignore_ascii_case(LINK_TYPE_TAG))
+            .find_map(|(_, value)| value.parse().ok())
+            .unwrap_or_default()
+    });
+
+    let mut kinds: BTreeMap<String, String> = link_type
+        .default_kind_infos()
+        .iter()
+        .map(|(name, value)| ((*name).to_string(), (*value).to_string()))
+        .collect();
+    let from_drawing = attributes
+        .iter()
+        .filter_map(|(tag, value)| Some((kind_name(tag)?.to_lowercase(), value.clone())));
+    let from_options = options
+        .kind_infos
+        .iter()
+        .map(|(name, value)| (name.to_lowercase(), value.clone()));
+    for (name, value) in from_drawing.chain(from_options) {
+        if !value.trim().is_empty() {
+            kinds.insert(name, value.trim().to_string());
+        }
+    }
+
+    //only the elements linked to others have kind informations
+    let kinds = (link_type != LinkType::Simple && !kinds.is_empty()).then_some(KindInfos(kinds));
+    (link_type, kinds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{element_kind, LinkType};
+    use crate::ConversionOptions;
+    use dxf::entities::{AttributeDefinition, Entity, EntityType};
+    use dxf::Drawing;
+
+    fn drawing(attdefs: &[(&str, &str)]) -> Drawing {
+        let mut drw = Drawing::new();
+        for (tag, value) in attdefs {
+            drw.add_entity(Entity::new(EntityType::AttributeDefinition(
+                AttributeDefinition {
+                    text_tag: (*tag).to_string(),
+                    value: (*value).to_string(),
+                    ..Default::default()
+                },
+            )));
+        }
+        drw
+    }
+
+    fn kinds(drw: &Drawing, options: &ConversionOptions) -> (LinkType, Vec<(String, String)>) {
+        let (link_type, kinds) = element_kind(drw, options);
+        (
+            link_type,
+            kinds
+                .map(|kinds| kinds.0.into_iter().collect())
+                .unwrap_or_default(),
+        )
+    }
+
+    fn pairs(kinds: &[(&str, &str)]) -> Vec<(String, String)> {
+        kinds
+            .iter()
+            .map(|(name, value)| ((*name).to_string(), (*value).to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn slave_defaults_are_filled_in() {
+        let drw = drawing(&[("LINK_TYPE", "Slave"), ("KIND_STATE", "NC")]);
+        let (link_type, kinds) = kinds(&drw, &ConversionOptions::default());
+        assert_eq!(link_type, LinkType::Slave);
+        assert_eq!(
+            kinds,
+            pairs(&[("number", "1"), ("state", "NC"), ("type", "simple")])
+        );
+    }
+
+    #[test]
+    fn options_win_over_the_drawing() {
+        let drw = drawing(&[("LINK_TYPE", "slave"), ("KIND_STATE", "NC")]);
+        let options = ConversionOptions {
+            link_type: Some(LinkType::Master),
+            kind_infos: [("STATE".to_string(), "NO".to_string())].into(),
+            ..Default::default()
+        };
+        let (link_type, kinds) = kinds(&drw, &options);
+        assert_eq!(link_type, LinkType::Master);
+        assert_eq!(kinds, pairs(&[("state", "NO"), ("type", "coil")]));
+    }
+
+    #[test]
+    fn simple_has_no_kinds() {
+        let drw = drawing(&[("KIND_STATE", "NC")]);
+        let (link_type, kinds) = kinds(&drw, &ConversionOptions::default());
+        assert_eq!(link_type, LinkType::Simple);
+        assert!(kinds.is_empty());
+    }
+}
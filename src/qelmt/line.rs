@@ -1,8 +1,14 @@
+use super::error::ConversionError;
+use super::raster::{polyline, Canvas, Paint};
+use super::style::{LineWeight, Style};
+use super::svg::{add_style, PatternFills, ToSvg};
 use super::two_dec;
 use super::LineEnd;
-use super::ScaleEntity;
+use super::{BoundingBox, Bounds};
+use super::{Objects, Transform, TransformEntity};
 use dxf::entities::{self, LwPolyline, Polyline};
 use simple_xml_builder::XMLElement;
+use std::fmt::Write;
 
 #[derive(Debug)]
 pub struct Line {
@@ -16,7 +22,7 @@ pub struct Line {
     pub x2: f64,
     pub y2: f64,
 
-    style: String,
+    pub(crate) style: Style,
     end1: LineEnd,
     antialias: bool,
 }
@@ -38,22 +44,37 @@ impl From<&entities::Line> for Line {
             //in the original code antialias is always set to false...I'm guessing for performance
             //reasons...I'm trying to think if there is a time we might want to turn it on?
             antialias: false,
-            style: if line.thickness > 0.5 {
-                "line-style:normal;line-weight:normal;filling:none;color:black"
+            style: Style::default(),
+        }
+    }
+}
+
+impl Line {
+    //simple arrows on the ends of the line, `size` being the length of the arrow heads
+    pub(crate) fn arrows(self, start: bool, end: bool, size: f64) -> Self {
+        let end_type = |arrow: bool| {
+            if arrow {
+                LineEnd::SimpleArrow
             } else {
-                "line-style:normal;line-weight:thin;filling:none;color:black"
+                LineEnd::None
             }
-            .into(),
+        };
+        Line {
+            end1: end_type(start),
+            length1: size,
+            end2: end_type(end),
+            length2: size,
+            ..self
         }
     }
 }
 
 impl TryFrom<&Polyline> for Line {
-    type Error = &'static str; //add better error later
+    type Error = ConversionError;
 
     fn try_from(poly: &Polyline) -> Result<Self, Self::Error> {
         if poly.__vertices_and_handles.len() != 2 {
-            return Err("Error can't convert polyline with more than 2 points into a Line");
+            return Err(ConversionError::NotALine(poly.__vertices_and_handles.len()));
         }
 
         Ok(Line {
@@ -69,22 +90,17 @@ impl TryFrom<&Polyline> for Line {
             //in the original code antialias is always set to false...I'm guessing for performance
             //reasons...I'm trying to think if there is a time we might want to turn it on?
             antialias: false,
-            style: if poly.thickness > 0.5 {
-                "line-style:normal;line-weight:normal;filling:none;color:black"
-            } else {
-                "line-style:normal;line-weight:thin;filling:none;color:black"
-            }
-            .into(),
+            style: Style::default(),
         })
     }
 }
 
 impl TryFrom<&LwPolyline> for Line {
-    type Error = &'static str; //add better error later
+    type Error = ConversionError;
 
     fn try_from(poly: &LwPolyline) -> Result<Self, Self::Error> {
         if poly.vertices.len() != 2 {
-            return Err("Error can't convert polyline with more than 2 points into a Line");
+            return Err(ConversionError::NotALine(poly.vertices.len()));
         }
 
         Ok(Line {
@@ -100,12 +116,7 @@ impl TryFrom<&LwPolyline> for Line {
             //in the original code antialias is always set to false...I'm guessing for performance
             //reasons...I'm trying to think if there is a time we might want to turn it on?
             antialias: false,
-            style: if poly.thickness > 0.1 {
-                "line-style:normal;line-weight:normal;filling:none;color:black"
-            } else {
-                "line-style:normal;line-weight:thin;filling:none;color:black"
-            }
-            .into(),
+            style: Style::default(),
         })
     }
 }
@@ -140,13 +151,11 @@ impl From<&entities::Leader> for Leader {
                         //looks like line thickenss and color information I *might* need to grab from a dimension style
                         //entity which I haven't implemented yet
                         /*style: if line.thickness > 0.5 {
-                            "line-style:normal;line-weight:normal;filling:none;color:black"
+                            Style::weight(LineWeight::Normal)
                         } else {
-                            "line-style:normal;line-weight:thin;filling:none;color:black"
-                        }
-                        .into(),*/
-                        style: "line-style:normal;line-weight:normal;filling:none;color:black"
-                            .into(),
+                            Style::weight(LineWeight::Thin)
+                        },*/
+                        style: Style::weight(LineWeight::Normal),
                     }
                 })
                 .collect(),
@@ -171,13 +180,10 @@ impl From<&Line> for XMLElement {
     }
 }
 
-impl ScaleEntity for Line {
-    fn scale(&mut self, fact_x: f64, fact_y: f64) {
-        self.x1 *= fact_x;
-        self.x2 *= fact_x;
-
-        self.y1 *= fact_y;
-        self.y2 *= fact_y;
+impl TransformEntity for Line {
+    fn transform(mut self, tf: &Transform) -> Objects {
+        (self.x1, self.y1) = tf.apply_qet(self.x1, self.y1);
+        (self.x2, self.y2) = tf.apply_qet(self.x2, self.y2);
 
         //while writing this scaling code, I'm looking at
         //QET_ElementScaler from plc-user to see if there are
@@ -186,26 +192,87 @@ impl ScaleEntity for Line {
         //to 99.0, but I'm not sure why at the moment. I'll go
         //ahead and limit them as well, and try to come back to
         //figure out what the purpose here is
-        self.length1 *= fact_x.min(fact_y);
-        self.length1 = self.length1.min(99.0);
+        let fact = tf.scale_x().min(tf.scale_y());
+        self.length1 = (self.length1 * fact).min(99.0);
+        self.length2 = (self.length2 * fact).min(99.0);
 
-        self.length2 *= fact_x.min(fact_y);
-        self.length2 = self.length2.min(99.0);
+        Objects::Line(self)
     }
+}
 
-    fn left_bound(&self) -> f64 {
-        self.x1.min(self.x2)
+impl Bounds for Line {
+    fn bounding_box(&self) -> BoundingBox {
+        BoundingBox::from_points([(self.x1, self.y1), (self.x2, self.y2)])
     }
+}
+
+impl Line {
+    //A simple arrow is two strokes going back from the tip, as long as the arrow is and
+    //spread out half of that to either side, like QET draws them. Each one is the
+    //three points of the arrow, with the tip in the middle
+    pub(crate) fn arrow_heads(&self) -> Vec<[(f64, f64); 3]> {
+        let len = (self.x2 - self.x1).hypot(self.y2 - self.y1);
+        if len <= 0.0 {
+            return Vec::new();
+        }
 
-    fn right_bound(&self) -> f64 {
-        self.x1.max(self.x2)
+        let (ux, uy) = ((self.x2 - self.x1) / len, (self.y2 - self.y1) / len);
+        [
+            (&self.end1, self.x1, self.y1, self.length1, 1.0),
+            (&self.end2, self.x2, self.y2, self.length2, -1.0),
+        ]
+        .into_iter()
+        .filter(|(end, ..)| matches!(end, LineEnd::SimpleArrow))
+        .map(|(_, x, y, size, dir)| {
+            let (bx, by) = (x + ux * size * dir, y + uy * size * dir);
+            let (nx, ny) = (-uy * size / 2.0, ux * size / 2.0);
+            [(bx + nx, by + ny), (x, y), (bx - nx, by - ny)]
+        })
+        .collect()
     }
+}
+
+impl ToSvg for Line {
+    fn to_svg(&self, fills: &mut PatternFills) -> XMLElement {
+        let mut path = format!(
+            "M{},{} L{},{}",
+            two_dec(self.x1),
+            two_dec(self.y1),
+            two_dec(self.x2),
+            two_dec(self.y2)
+        );
 
-    fn top_bound(&self) -> f64 {
-        self.y1.min(self.y2)
+        for [(x1, y1), (x, y), (x2, y2)] in self.arrow_heads() {
+            //writing to a String can't fail
+            let _ = write!(
+                path,
+                " M{},{} L{},{} L{},{}",
+                two_dec(x1),
+                two_dec(y1),
+                two_dec(x),
+                two_dec(y),
+                two_dec(x2),
+                two_dec(y2)
+            );
+        }
+
+        let mut line_svg = XMLElement::new("path");
+        line_svg.add_attribute("d", path);
+        add_style(&mut line_svg, &self.style, fills);
+        //the arrows are only ever strokes, whatever the filling is
+        line_svg.add_attribute("fill", "none");
+        line_svg
     }
+}
 
-    fn bot_bound(&self) -> f64 {
-        self.y1.max(self.y2)
+impl Paint for Line {
+    fn paint(&self, canvas: &mut Canvas) {
+        let segments = std::iter::once(vec![(self.x1, self.y1), (self.x2, self.y2)])
+            .chain(self.arrow_heads().into_iter().map(Vec::from));
+        for segment in segments {
+            if let Some(path) = polyline(segment, false) {
+                canvas.stroke(&path, &self.style);
+            }
+        }
     }
 }
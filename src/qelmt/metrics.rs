@@ -0,0 +1,170 @@
+use super::{BoundingBox, FontInfo, HAlignment, VAlignment};
+#[cfg(not(target_arch = "wasm32"))]
+use std::cell::RefCell;
+#[cfg(not(target_arch = "wasm32"))]
+use std::collections::{HashMap, HashSet};
+#[cfg(not(target_arch = "wasm32"))]
+use tracing::warn;
+use unicode_segmentation::UnicodeSegmentation;
+
+//how wide a character is on average compared to the font size, when there's no font to ask
+const CHAR_WIDTH: f64 = 0.75;
+
+/// How much room a piece of text takes up, before it's rotated
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct TextSize {
+    pub width: f64,
+    pub height: f64,
+}
+
+impl TextSize {
+    /// The distance from one line to the next as a multiple of the font size, about what Qt ends up with
+    pub const LINE_SPACING: f64 = 1.2;
+
+    /// Lays the text out with the actual font when `font_metrics` is set and the font (or one
+    /// standing in for it) can be found, otherwise falls back on [`TextSize::estimate`]
+    pub fn measure(text: &str, font: &FontInfo, font_metrics: bool) -> Self {
+        if font_metrics {
+            if let Some(size) = layout_size(text, font) {
+                return size;
+            }
+        }
+        Self::estimate(text, font.point_size)
+    }
+
+    /// A guess based on the number of characters in the longest line, which
+    /// knows nothing about the font so it's only ever roughly right
+    #[allow(clippy::cast_precision_loss)]
+    pub fn estimate(text: &str, point_size: f64) -> Self {
+        let longest = text
+            .lines()
+            .map(|line| line.graphemes(true).count())
+            .max()
+            .unwrap_or_default();
+        let lines = text.lines().count().max(1);
+        Self {
+            width: longest as f64 * point_size * CHAR_WIDTH,
+            height: lines as f64 * point_size * Self::LINE_SPACING,
+        }
+    }
+
+    /// The box around the text once it's placed. The alignments say
+    /// where (x, y) is on the text, and it's rotated (clockwise, like QET) around that point
+    pub fn extents(
+        &self,
+        x: f64,
+        y: f64,
+        h_alignment: &HAlignment,
+        v_alignment: &VAlignment,
+        rotation: f64,
+    ) -> BoundingBox {
+        let left = match h_alignment {
+            HAlignment::Left => 0.0,
+            HAlignment::Center => -self.width / 2.0,
+            HAlignment::Right => -self.width,
+        };
+        let top = match v_alignment {
+            VAlignment::Top => 0.0,
+            VAlignment::Center => -self.height / 2.0,
+            VAlignment::Bottom => -self.height,
+        };
+        self.placed(x, y, left, top, rotation)
+    }
+
+    /// Same as [`TextSize::extents`], with the top left corner of the
+    /// text at (left, top) from the point it rotates around
+    pub fn placed(&self, x: f64, y: f64, left: f64, top: f64, rotation: f64) -> BoundingBox {
+        let (sin, cos) = rotation.to_radians().sin_cos();
+        let corners = [
+            (left, top),
+            (left + self.width, top),
+            (left, top + self.height),
+            (left + self.width, top + self.height),
+        ]
+        .map(|(dx, dy)| (x + dx * cos - dy * sin, y + dx * sin + dy * cos));
+
+        BoundingBox::from_points(corners)
+    }
+}
+
+//Loading the system fonts is slow, so it's only done once (per thread). Laying out
+//the text isn't cheap either, and the same text gets measured over and over while
+//working out the bounds, so the sizes are kept by (text, font family, font size)
+#[cfg(not(target_arch = "wasm32"))]
+thread_local! {
+    static FONT_CONTEXTS: RefCell<(parley::FontContext, parley::LayoutContext<()>)> =
+        RefCell::new((parley::FontContext::new(), parley::LayoutContext::new()));
+    static SIZES: RefCell<HashMap<(String, String, u64), Option<TextSize>>> =
+        RefCell::new(HashMap::new());
+    static MISSING_FONTS: RefCell<HashSet<String>> = RefCell::new(HashSet::new());
+}
+
+/// Lays the text out with the font (or the closest one that's installed), for
+/// measuring it or drawing the glyphs. None if there's nothing to lay out
+#[cfg(not(target_arch = "wasm32"))]
+#[allow(clippy::cast_possible_truncation)]
+pub(super) fn with_layout<R>(
+    text: &str,
+    font: &FontInfo,
+    f: impl FnOnce(&mut parley::Layout<()>) -> R,
+) -> Option<R> {
+    use parley::style::{FontStack, StyleProperty};
+
+    if text.trim().is_empty() || font.point_size <= 0.0 {
+        return None;
+    }
+
+    //QET ships osifont, but it's not likely to be installed on the machine doing the
+    //conversion, in which case a sans-serif font is a lot closer than nothing
+    let stack = format!("\"{}\", osifont, sans-serif", font.family);
+    FONT_CONTEXTS.with(|contexts| {
+        let (fcx, lcx) = &mut *contexts.borrow_mut();
+        if fcx.collection.family_by_name(&font.family).is_none() {
+            warn_missing_font(&font.family);
+        }
+        let mut builder = lcx.ranged_builder(fcx, text, 1.0);
+        builder.push_default(StyleProperty::FontStack(FontStack::Source(stack.into())));
+        builder.push_default(StyleProperty::FontSize(font.point_size as f32));
+        builder.push_default(StyleProperty::LineHeight(TextSize::LINE_SPACING as f32));
+        let mut layout = builder.build(text);
+        layout.break_all_lines(None);
+
+        //no fonts at all on the system, the layout doesn't have anything in it
+        (layout.width() > 0.0).then(|| f(&mut layout))
+    })
+}
+
+//once per font, there's usually a lot of texts using the same one
+#[cfg(not(target_arch = "wasm32"))]
+fn warn_missing_font(family: &str) {
+    MISSING_FONTS.with(|missing| {
+        if missing.borrow_mut().insert(family.to_string()) {
+            warn!("the font {family} isn't installed, the texts using it are measured with a stand in font");
+        }
+    });
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn layout_size(text: &str, font: &FontInfo) -> Option<TextSize> {
+    let key = (
+        text.to_string(),
+        font.family.clone(),
+        font.point_size.to_bits(),
+    );
+    if let Some(size) = SIZES.with(|sizes| sizes.borrow().get(&key).copied()) {
+        return size;
+    }
+
+    let size = with_layout(text, font, |layout| TextSize {
+        width: f64::from(layout.width()),
+        height: f64::from(layout.height()),
+    });
+    SIZES.with(|sizes| sizes.borrow_mut().insert(key, size));
+    size
+}
+
+//There's no way to get at the system fonts from the browser
+#[cfg(target_arch = "wasm32")]
+fn layout_size(_text: &str, _font: &FontInfo) -> Option<TextSize> {
+    None
+}
@@ -1,17 +1,21 @@
-use dxf::entities::{AttributeDefinition, Entity, EntityType};
+use dxf::entities::{AttributeDefinition, Entity, EntityCommon, EntityType};
 use dxf::entities::{LwPolyline, Polyline};
 use dxf::enums::{AttachmentPoint, HorizontalTextJustification, Units, VerticalTextJustification};
 use dxf::{Block, Drawing};
-use dynamictext::DTextBuilder;
+use dynamictext::{normalize_mtext, DTextBuilder};
 use hex_color::HexColor;
 use itertools::Itertools;
 use simple_xml_builder::XMLElement;
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::f64::consts::PI;
 use std::fmt::Display;
 use uuid::Uuid;
 
-use tracing::{error, info, span, trace, Level};
+use tracing::{error, info, span, trace, warn, Level};
+
+use crate::ConversionOptions;
 
 pub mod arc;
 pub use arc::Arc;
@@ -20,27 +24,63 @@ pub mod line;
 pub use line::{Leader, Line};
 
 pub mod text;
-pub use text::Text;
+pub use text::{Text, TextMode};
 
 pub mod dynamictext;
 pub use dynamictext::DynamicText;
 
+mod metrics;
+
 pub mod polygon;
+use polygon::BulgedPolyline;
 pub use polygon::Polygon;
 
 pub mod ellipse;
+use ellipse::DxfEllipse;
 pub use ellipse::Ellipse;
 
-fn find_block<'a>(drw: &'a Drawing, name: &str) -> Option<&'a Block> {
-    //this is ugly there has to be a cleaner way to filter this....but for my first attempt at pulling the
-    //blocks out of the drawing it works.
-    //I mean would this ever return more than 1? I would assume block names have to be unique?
-    //but maybe not, the blocks have a handle, which is a u64. There is a get by handle function
-    //but not a get by name function....maybe the handle is what is unique and there can be duplicate names?
-    //a quick glance through the dxf code it looks like the handle might be given to the library user when inserting
-    //an entity? So I don't think there is any easy way to get the handle
-    drw.blocks().filter(|bl| bl.name == name).take(1).next()
-}
+pub mod info;
+
+pub mod kind;
+
+pub mod names;
+use kind::KindInfos;
+pub use kind::LinkType;
+use names::Names;
+
+pub mod terminal;
+pub use terminal::{Terminal, TerminalRules};
+
+pub mod transform;
+pub use transform::Transform;
+
+pub mod boundingbox;
+pub use boundingbox::BoundingBox;
+
+pub mod error;
+use error::entity_type_name;
+pub use error::{ConversionError, EntityError};
+
+pub mod report;
+use report::Outcome;
+pub use report::Report;
+
+pub mod style;
+
+pub mod color;
+
+mod svg;
+use svg::{PatternFills, ToSvg};
+
+mod raster;
+use raster::{Canvas, Paint};
+
+pub mod hatch;
+pub use hatch::Hatches;
+
+pub mod dimension;
+use dimension::{dimension_base, DimStyle, Dimension};
+use style::{ByBlock, StyleResolver};
 
 #[derive(Debug)]
 enum Either<L, R> {
@@ -59,26 +99,23 @@ pub struct Definition {
     link_type: LinkType,
     uuid: ElmtUuid,
     names: Names,
+    kind_infos: Option<KindInfos>,
     element_infos: Option<ElemInfos>,
     informations: &'static str,
     description: Description,
     //counts
 }
 
-//Since the ScaleEntity trait was added to all the objects/elements
-//and I need to add the get bounds to all it probably makes sense to have
-
-//them all within the same trait instead of multiple traits, as a collective
-//set of functions needed by the objects...but I should probably come up with
-//a better trait name then. For now I'll leave it and just get the code working
-trait ScaleEntity {
-    fn scale(&mut self, fact_x: f64, fact_y: f64);
-
-    fn left_bound(&self) -> f64;
-    fn right_bound(&self) -> f64;
+//Placing an object with a Transform (block inserts, the drawing unit scaling)
+//can leave it as something QET can't represent natively anymore, a QET ellipse or arc
+//can't be rotated for example. So the transform consumes the object and gives back
+//whatever it ended up as.
+trait TransformEntity {
+    fn transform(self, tf: &Transform) -> Objects;
+}
 
-    fn top_bound(&self) -> f64;
-    fn bot_bound(&self) -> f64;
+trait Bounds {
+    fn bounding_box(&self) -> BoundingBox;
 }
 
 trait Circularity {
@@ -163,20 +200,63 @@ impl Circularity for LwPolyline {
 }
 
 impl Definition {
-    pub fn new(name: impl Into<String>, spline_step: u32, drw: &Drawing) -> Self {
+    pub fn new(
+        name: impl Into<String>,
+        drw: &Drawing,
+        hatches: &Hatches,
+        options: &ConversionOptions,
+    ) -> Self {
+        Self::with_contents(name, drw, None, hatches, options)
+    }
+
+    /// Same as [`Definition::new`], along with the raw contents of the .dxf the drawing was
+    /// loaded from, which a deterministic conversion makes the uuids out of (see [`element_uuid`])
+    pub fn with_contents(
+        name: impl Into<String>,
+        drw: &Drawing,
+        contents: Option<&[u8]>,
+        hatches: &Hatches,
+        options: &ConversionOptions,
+    ) -> Self {
         /*for st in drw.styles() {
             dbg!(st);
         }*/
+        let name = name.into();
+        let uuid = options.deterministic.then(|| {
+            if let Some(contents) = contents {
+                element_uuid(&name, contents)
+            } else {
+                //without the file, the drawing written back out is the next best thing.
+                //Writing into memory doesn't fail, and if it somehow did it would still
+                //be the same every time
+                let mut saved = Vec::new();
+                let _ = drw.save(&mut saved);
+                element_uuid(&name, &saved)
+            }
+        });
+
         let scale_factor = Self::scale_factor(drw.header.default_drawing_units);
-        let description = {
-            let mut description: Description = (drw, spline_step).into();
-            description.scale(scale_factor, scale_factor);
+        let mut description = {
+            let mut description: Description = (drw, hatches, options, uuid).into();
+            description.transform(&Transform::scaling(scale_factor, scale_factor));
             description
         };
 
+        //an empty drawing still gets an element, just an empty one around the origin
+        let bbox = match description.bounding_box() {
+            bbox if bbox.is_empty() => BoundingBox {
+                left: 0.0,
+                top: 0.0,
+                right: 0.0,
+                bottom: 0.0,
+            },
+            bbox => bbox,
+        };
+        description.orient_terminals(&bbox);
+
         //The below calculation for width and hotspot_x are taken from the qet source code
         let (width, hotspot_x) = {
-            let tmp_width = description.right_bound() - description.left_bound();
+            let tmp_width = bbox.width();
             let int_width = tmp_width.round() as i64;
             let upwidth = ((int_width / 10) * 10) + 10;
             let xmargin = (upwidth as f64 - tmp_width).round();
@@ -187,15 +267,12 @@ impl Definition {
                 upwidth
             };
 
-            (
-                width,
-                -((description.left_bound() - (xmargin / 2.0)).round() as i64),
-            )
+            (width, -((bbox.left - (xmargin / 2.0)).round() as i64))
         };
 
         //The below calculation for height and hotspot_y are taken from the qet source code
         let (height, hotspot_y) = {
-            let tmp_height = description.bot_bound() - description.top_bound();
+            let tmp_height = bbox.height();
             let int_height = tmp_height.round() as i64;
             let upheight = ((int_height / 10) * 10) + 10;
             let ymargin = (upheight as f64 - tmp_height).round();
@@ -206,12 +283,11 @@ impl Definition {
                 upheight
             };
 
-            (
-                height,
-                -((description.top_bound() - (ymargin / 2.0)).round() as i64),
-            )
+            (height, -((bbox.top - (ymargin / 2.0)).round() as i64))
         };
 
+        let (link_type, kind_infos) = kind::element_kind(drw, options);
+
         Definition {
             r#type: ItemType::Element,
             width,
@@ -219,20 +295,83 @@ impl Definition {
             hotspot_x,
             hotspot_y,
             version: "0.8.0".into(),
-            link_type: LinkType::Simple,
-            uuid: Uuid::new_v4().into(),
-            names: Names {
-                names: vec![Name {
-                    lang: "en".into(),
-                    value: name.into(), //need to truncate the extension
-                }],
-            },
-            element_infos: None,
+            link_type,
+            uuid: uuid.unwrap_or_else(Uuid::new_v4).into(),
+            names: names::element_names(name, drw, options),
+            kind_infos,
+            element_infos: info::element_infos(drw, &options.element_info),
             informations: "Created using dxf2elmt!",
             description,
         }
     }
 
+    /// What happened to each of the entities in the drawing
+    pub fn report(&self) -> &Report {
+        &self.description.report
+    }
+
+    /// The entities that were left out of the element, and why
+    pub fn warnings(&self) -> Vec<EntityError> {
+        self.description.report.warnings()
+    }
+
+    /// The element drawn as SVG, with the hotspot marked, to see the result without opening QET
+    #[must_use]
+    pub fn svg(&self) -> XMLElement {
+        let mut fills = PatternFills::default();
+        let mut shapes = XMLElement::new("g");
+        for obj in &self.description.objects {
+            shapes.add_child(obj.to_svg(&mut fills));
+        }
+
+        let mut svg_xml = XMLElement::new("svg");
+        svg_xml.add_attribute("xmlns", "http://www.w3.org/2000/svg");
+        svg_xml.add_attribute(
+            "viewBox",
+            format!(
+                "{} {} {} {}",
+                -self.hotspot_x, -self.hotspot_y, self.width, self.height
+            ),
+        );
+        svg_xml.add_attribute("width", self.width);
+        svg_xml.add_attribute("height", self.height);
+        svg_xml.add_child(fills.defs());
+
+        //the edge of the element, same as the element editor shows it
+        let mut outline = XMLElement::new("rect");
+        outline.add_attribute("x", -self.hotspot_x);
+        outline.add_attribute("y", -self.hotspot_y);
+        outline.add_attribute("width", self.width);
+        outline.add_attribute("height", self.height);
+        outline.add_attribute("fill", "#FFFFFF");
+        outline.add_attribute("stroke", "#C0C0C0");
+        outline.add_attribute("stroke-width", 0.5);
+        outline.add_attribute("stroke-dasharray", "2,2");
+        svg_xml.add_child(outline);
+
+        svg_xml.add_child(shapes);
+        svg_xml.add_child(svg::hotspot());
+        svg_xml
+    }
+
+    /// The element drawn into a PNG, with each mm of the element (2 QET units) coming out
+    /// as `dpi` / 25.4 pixels. The outline and hotspot the SVG preview has are left out, it's
+    /// only the drawing. None if it can't be drawn, when it's empty or far too big
+    #[must_use]
+    pub fn thumbnail(&self, dpi: f64) -> Option<Vec<u8>> {
+        let mut canvas = Canvas::new(
+            -self.hotspot_x,
+            -self.hotspot_y,
+            self.width,
+            self.height,
+            dpi,
+        )?;
+        for obj in &self.description.objects {
+            obj.paint(&mut canvas);
+        }
+        canvas.into_png()
+    }
+
     fn scale_factor(unit: Units) -> f64 {
         //so per discussion at https://qelectrotech.org/forum/viewtopic.php?pid=20685#p20685
         //we are in agreement to scale things to 1mm = 2px;
@@ -281,11 +420,14 @@ impl From<&Definition> for XMLElement {
         def_xml.add_attribute("hotspot_x", def.hotspot_x);
         def_xml.add_attribute("hotspot_y", def.hotspot_y);
         def_xml.add_attribute("version", &def.version);
-        def_xml.add_attribute("link_type", &def.link_type);
+        def_xml.add_attribute("link_type", def.link_type);
         def_xml.add_attribute("type", &def.r#type);
 
         def_xml.add_child((&def.uuid).into());
         def_xml.add_child((&def.names).into());
+        if let Some(kinds) = &def.kind_infos {
+            def_xml.add_child(kinds.into());
+        }
         if let Some(einfos) = &def.element_infos {
             def_xml.add_child(einfos.into());
         }
@@ -308,7 +450,7 @@ pub(crate) enum Objects {
     DynamicText(DynamicText),
     Text(Text),
     Line(Line),
-    //Terminal(Terminal),
+    Terminal(Terminal),
     Group(Vec<Objects>),
 }
 
@@ -364,141 +506,95 @@ impl<'a> Iterator for Children<'a> {
     }
 }
 
-impl ScaleEntity for Objects {
-    fn scale(&mut self, fact_x: f64, fact_y: f64) {
+impl Objects {
+    //only the geometry has a style, the texts are left alone
+    fn restyle(&mut self, f: &impl Fn(&mut style::Style)) {
         match self {
-            Objects::Arc(arc) => arc.scale(fact_x, fact_y),
-            Objects::Ellipse(ellipse) => ellipse.scale(fact_x, fact_y),
-            Objects::Polygon(polygon) => polygon.scale(fact_x, fact_y),
-            Objects::DynamicText(dynamic_text) => dynamic_text.scale(fact_x, fact_y),
-            Objects::Text(text) => text.scale(fact_x, fact_y),
-            Objects::Line(line) => line.scale(fact_x, fact_y),
-            Objects::Group(vec) => vec.iter_mut().for_each(|ob| ob.scale(fact_x, fact_y)),
+            Objects::Arc(arc) => f(&mut arc.style),
+            Objects::Ellipse(ellipse) => f(&mut ellipse.style),
+            Objects::Polygon(polygon) => f(&mut polygon.style),
+            Objects::Line(line) => f(&mut line.style),
+            Objects::DynamicText(_) | Objects::Text(_) | Objects::Terminal(_) => {}
+            Objects::Group(vec) => vec.iter_mut().for_each(|ob| ob.restyle(f)),
         }
     }
 
-    fn left_bound(&self) -> f64 {
+    //the terminals can only tell which way they point once the whole element is there
+    fn orient_terminals(&mut self, bbox: &BoundingBox) {
         match self {
-            Objects::Arc(arc) => arc.left_bound(),
-            Objects::Ellipse(ellipse) => ellipse.left_bound(),
-            Objects::Polygon(polygon) => polygon.left_bound(),
-            Objects::DynamicText(dynamic_text) => dynamic_text.left_bound(),
-            Objects::Text(text) => text.left_bound(),
-            Objects::Line(line) => line.left_bound(),
-            Objects::Group(vec) => {
-                let lb = vec.iter().min_by(|ob1, ob2| {
-                    ob1.left_bound()
-                        .partial_cmp(&ob2.left_bound())
-                        .unwrap_or(std::cmp::Ordering::Greater)
-                });
-
-                if let Some(lb) = lb {
-                    lb.left_bound()
-                } else {
-                    0.0
-                }
-            }
+            Objects::Terminal(term) => term.orient(bbox),
+            Objects::Group(vec) => vec.iter_mut().for_each(|ob| ob.orient_terminals(bbox)),
+            _ => {}
         }
     }
 
-    fn right_bound(&self) -> f64 {
+    //gives the parts that have a uuid the next one from `next`, in the order they're written out
+    fn set_uuids(&mut self, next: &mut impl FnMut() -> Uuid) {
         match self {
-            Objects::Arc(arc) => arc.right_bound(),
-            Objects::Ellipse(ellipse) => ellipse.right_bound(),
-            Objects::Polygon(polygon) => polygon.right_bound(),
-            Objects::DynamicText(dynamic_text) => dynamic_text.right_bound(),
-            Objects::Text(text) => text.right_bound(),
-            Objects::Line(line) => line.right_bound(),
-            Objects::Group(vec) => {
-                let rb = vec.iter().max_by(|ob1, ob2| {
-                    ob1.right_bound()
-                        .partial_cmp(&ob2.right_bound())
-                        .unwrap_or(std::cmp::Ordering::Less)
-                });
-
-                if let Some(rb) = rb {
-                    rb.right_bound()
-                } else {
-                    0.0
-                }
-            }
+            Objects::DynamicText(dtext) => dtext.uuid = next(),
+            Objects::Terminal(term) => term.uuid = next(),
+            Objects::Group(vec) => vec.iter_mut().for_each(|ob| ob.set_uuids(next)),
+            Objects::Arc(_)
+            | Objects::Ellipse(_)
+            | Objects::Polygon(_)
+            | Objects::Text(_)
+            | Objects::Line(_) => {}
         }
     }
+}
 
-    fn top_bound(&self) -> f64 {
+impl TransformEntity for Objects {
+    fn transform(self, tf: &Transform) -> Objects {
         match self {
-            Objects::Arc(arc) => arc.top_bound(),
-            Objects::Ellipse(ellipse) => ellipse.top_bound(),
-            Objects::Polygon(polygon) => polygon.top_bound(),
-            Objects::DynamicText(dynamic_text) => dynamic_text.top_bound(),
-            Objects::Text(text) => text.top_bound(),
-            Objects::Line(line) => line.top_bound(),
+            Objects::Arc(arc) => arc.transform(tf),
+            Objects::Ellipse(ellipse) => ellipse.transform(tf),
+            Objects::Polygon(polygon) => polygon.transform(tf),
+            Objects::DynamicText(dynamic_text) => dynamic_text.transform(tf),
+            Objects::Text(text) => text.transform(tf),
+            Objects::Line(line) => line.transform(tf),
+            Objects::Terminal(term) => term.transform(tf),
             Objects::Group(vec) => {
-                let tb = vec.iter().min_by(|ob1, ob2| {
-                    ob1.top_bound()
-                        .partial_cmp(&ob2.top_bound())
-                        .unwrap_or(std::cmp::Ordering::Greater)
-                });
-
-                if let Some(tb) = tb {
-                    tb.top_bound()
-                } else {
-                    0.0
-                }
+                Objects::Group(vec.into_iter().map(|ob| ob.transform(tf)).collect())
             }
         }
     }
+}
 
-    fn bot_bound(&self) -> f64 {
+impl Bounds for Objects {
+    fn bounding_box(&self) -> BoundingBox {
         match self {
-            Objects::Arc(arc) => arc.bot_bound(),
-            Objects::Ellipse(ellipse) => ellipse.bot_bound(),
-            Objects::Polygon(polygon) => polygon.bot_bound(),
-            Objects::DynamicText(dynamic_text) => dynamic_text.bot_bound(),
-            Objects::Text(text) => text.bot_bound(),
-            Objects::Line(line) => line.bot_bound(),
-            Objects::Group(vec) => {
-                let bb = vec.iter().max_by(|ob1, ob2| {
-                    ob1.bot_bound()
-                        .partial_cmp(&ob2.bot_bound())
-                        .unwrap_or(std::cmp::Ordering::Less)
-                });
-
-                if let Some(bb) = bb {
-                    bb.bot_bound()
-                } else {
-                    0.0
-                }
-            }
+            Objects::Arc(arc) => arc.bounding_box(),
+            Objects::Ellipse(ellipse) => ellipse.bounding_box(),
+            Objects::Polygon(polygon) => polygon.bounding_box(),
+            Objects::DynamicText(dynamic_text) => dynamic_text.bounding_box(),
+            Objects::Text(text) => text.bounding_box(),
+            Objects::Line(line) => line.bounding_box(),
+            Objects::Terminal(term) => term.bounding_box(),
+            Objects::Group(vec) => vec.iter().fold(BoundingBox::EMPTY, |bbox, ob| {
+                bbox.union(&ob.bounding_box())
+            }),
         }
     }
 }
 
-#[derive(Debug)]
-struct ScaleFactor {
-    x: f64,
-    y: f64,
-}
-
-impl Default for ScaleFactor {
-    fn default() -> Self {
-        ScaleFactor { x: 1.0, y: 1.0 }
-    }
-}
-
-#[derive(Default, Debug)]
-pub(crate) struct Offset {
-    x: f64,
-    y: f64,
-}
+pub const DEFAULT_ELLIPSE_TOLERANCE: f64 = 0.01;
 
-#[derive(Debug)]
 pub struct ObjectsBuilder<'a> {
     ent: &'a Entity,
     spline_step: u32,
+    ellipse_tolerance: f64,
+    font_metrics: bool,
     blocks: &'a [&'a Block],
-    offset: Offset,
-    scale_fact: ScaleFactor,
+    hatches: Option<&'a Hatches>,
+    transform: Transform,
+    styles: Option<&'a StyleResolver<'a>>,
+    by_block: ByBlock,
+    report: Option<&'a RefCell<Report>>,
+    terminals: Option<&'a TerminalRules>,
+    element_info: Option<&'a HashMap<String, String>>,
+
+    //the names of the blocks the entity is in, for the report
+    block_path: Vec<String>,
 }
 
 impl<'a> ObjectsBuilder<'a> {
@@ -506,9 +602,33 @@ impl<'a> ObjectsBuilder<'a> {
         Self {
             ent,
             spline_step,
+            ellipse_tolerance: DEFAULT_ELLIPSE_TOLERANCE,
+            font_metrics: true,
             blocks: &[],
-            offset: Offset::default(),
-            scale_fact: ScaleFactor::default(),
+            hatches: None,
+            transform: Transform::default(),
+            styles: None,
+            by_block: ByBlock::default(),
+            report: None,
+            terminals: None,
+            element_info: None,
+            block_path: Vec::new(),
+        }
+    }
+
+    //how close the tessellated outline of a rotated ellipse needs to stay to the real thing
+    pub fn ellipse_tolerance(self, ellipse_tolerance: f64) -> Self {
+        Self {
+            ellipse_tolerance,
+            ..self
+        }
+    }
+
+    //measure texts with the font instead of guessing their size from the number of characters
+    pub fn font_metrics(self, font_metrics: bool) -> Self {
+        Self {
+            font_metrics,
+            ..self
         }
     }
 
@@ -516,243 +636,380 @@ impl<'a> ObjectsBuilder<'a> {
         Self { blocks, ..self }
     }
 
-    pub fn offsets(self, x: f64, y: f64) -> Self {
+    //the dxf crate doesn't read hatches, so the ones for the blocks are handed in separately
+    pub(crate) fn hatches(self, hatches: &'a Hatches) -> Self {
         Self {
-            offset: Offset { x, y },
+            hatches: Some(hatches),
             ..self
         }
     }
 
-    pub fn scaling(self, fact_x: f64, fact_y: f64) -> Self {
+    //without a resolver everything just gets the default style
+    pub(crate) fn styles(self, styles: Option<&'a StyleResolver<'a>>) -> Self {
+        Self { styles, ..self }
+    }
+
+    //what the insert containing the entity passes down to it
+    pub(crate) fn by_block(self, by_block: ByBlock) -> Self {
+        Self { by_block, ..self }
+    }
+
+    //where what happened to the entity (and the ones in its block) gets written down
+    pub(crate) fn report(self, report: &'a RefCell<Report>) -> Self {
         Self {
-            scale_fact: ScaleFactor {
-                x: fact_x,
-                y: fact_y,
-            },
+            report: Some(report),
             ..self
         }
     }
 
-    #[allow(clippy::too_many_lines)]
-    pub fn build(self) -> Result<Objects, &'static str /*add better error later*/> {
-        match &self.ent.specific {
-            EntityType::Circle(circle) => {
-                let mut ellipse: Ellipse = circle.into();
-
-                ellipse.scale(self.scale_fact.x, self.scale_fact.y);
-                ellipse.x += self.offset.x;
-                ellipse.y -= self.offset.y;
-                Ok(Objects::Ellipse(ellipse))
-            }
-            EntityType::Line(line) => {
-                let mut line: Line = line.into();
+    //which entities are terminals instead of being drawn, none of them without rules
+    pub fn terminals(self, terminals: Option<&'a TerminalRules>) -> Self {
+        Self { terminals, ..self }
+    }
 
-                line.scale(self.scale_fact.x, self.scale_fact.y);
+    //the attribute tags (and placeholder texts) that show an element information instead of their text
+    pub fn element_info(self, element_info: Option<&'a HashMap<String, String>>) -> Self {
+        Self {
+            element_info,
+            ..self
+        }
+    }
 
-                line.x1 += self.offset.x;
-                line.y1 -= self.offset.y;
+    //the transform from the coordinates of the entity to the drawing,
+    //which is only anything other than the identity for the entities inside a block
+    pub fn transform(self, transform: Transform) -> Self {
+        Self { transform, ..self }
+    }
 
-                line.x2 += self.offset.x;
-                line.y2 -= self.offset.y;
+    pub fn build(self) -> Result<Objects, EntityError> {
+        //a lot of the 2d entities are in their own coordinate system (which can be flipped
+        //around by the extrusion direction), get them into the drawing first
+        let builder = match Transform::entity_ocs(self.ent) {
+            Some(ocs) => Self {
+                transform: ocs.then(&self.transform),
+                ..self
+            },
+            None => self,
+        };
+        let res = builder.build_styled();
+        builder.record(&res);
+        res
+    }
 
-                Ok(Objects::Line(line))
+    fn record(&self, res: &Result<Objects, EntityError>) {
+        let outcome = match res {
+            Ok(obj) => self.outcome(obj),
+            Err(err) => {
+                warn!("{err}");
+                Outcome::dropped(&err.reason)
             }
-            EntityType::Arc(arc) => {
-                let mut arc: Arc = arc.into();
-
-                arc.scale(self.scale_fact.x, self.scale_fact.y);
+        };
+        if let Some(report) = self.report {
+            report.borrow_mut().add(
+                &self.ent.common,
+                entity_type_name(&self.ent.specific),
+                &self.block_path,
+                outcome,
+            );
+        }
+    }
 
-                arc.x += self.offset.x;
-                arc.y -= self.offset.y;
+    fn outcome(&self, obj: &Objects) -> Outcome {
+        //a terminal block stands for the terminal, nothing in it gets drawn
+        if matches!(obj, Objects::Terminal(_)) {
+            return Outcome::Converted {
+                parts: Outcome::parts(obj),
+            };
+        }
+        if let EntityType::Insert(ins) = &self.ent.specific {
+            return Outcome::Expanded {
+                block: ins.name.clone(),
+            };
+        }
+        if let Some(block) = self.dimension_block() {
+            return Outcome::Expanded {
+                block: block.name.clone(),
+            };
+        }
 
-                Ok(Objects::Arc(arc))
+        let parts = Outcome::parts(obj);
+        let reason = match &self.ent.specific {
+            EntityType::Spline(_) => Some("splines are made out of straight lines"),
+            EntityType::Arc(_) | EntityType::Circle(_) | EntityType::Ellipse(_)
+                if parts.contains_key("polygon") =>
+            {
+                Some("QET arcs and ellipses can't be rotated, so it's made out of straight lines")
             }
-            EntityType::Spline(spline) => {
-                let mut poly: Polygon = (spline, self.spline_step).into();
-
-                match poly.coordinates.len() {
-                    0 | 1 => Err("Error removing empty Spline"),
-                    //I'll need to improve my understanding of splines and the math here
-                    //to make sure I do this correctly.
-                    //2 => //convert to line
-                    _ => {
-                        poly.scale(self.scale_fact.x, self.scale_fact.y);
-                        for cord in &mut poly.coordinates {
-                            cord.x += self.offset.x;
-                            cord.y -= self.offset.y;
-                        }
-
-                        Ok(Objects::Polygon(poly))
-                    }
-                }
+            ent if dimension_base(ent).is_some() => {
+                Some("the dimension block is missing, so it's drawn from the definition points")
             }
-            EntityType::Text(text) => {
-                Ok(
-                    //right now the dxf2elmt defaults to making all text Static Text...
-                    //it was requested by the QET devs to add in support for Dynamic text
-                    //which was added, but it defaults to OFF, and QET doesn't pass the parameter
-                    //to enable it...I'm wondering if it makes more sense to default to use dynamic text
-                    //for now I'll set it to use dynamic text, and once I get the CLI flag passing through
-                    //I might change the default parameter to use Dynamic Text
-                    if false {
-                        //how best to pass in the flag for dynamic text or not....should the flag also default to true?
-                        let mut text: Text = (
-                            text,
-                            HexColor::from_u32(self.ent.common.color_24_bit as u32),
-                        )
-                            .into();
-
-                        text.scale(self.scale_fact.x, self.scale_fact.y);
-
-                        text.x += self.offset.x;
-                        text.y -= self.offset.y;
-
-                        Objects::Text(text)
-                    } else {
-                        let mut dtext = DTextBuilder::from_text(text)
-                            .color(HexColor::from_u32(self.ent.common.color_24_bit as u32))
-                            .build();
+            _ => None,
+        };
+        match reason {
+            Some(reason) => Outcome::Approximated {
+                parts,
+                reason: reason.into(),
+            },
+            None => Outcome::Converted { parts },
+        }
+    }
+
+    fn build_styled(&self) -> Result<Objects, EntityError> {
+        let mut obj = self
+            .build_entity()
+            .map_err(|reason| EntityError::new(self.ent, reason))?;
+
+        //the objects from an insert (or a dimension block) have already been styled based on their own entities
+        let is_insert = matches!(self.ent.specific, EntityType::Insert(_));
+        let from_block = is_insert || self.dimension_block().is_some();
+        if let (Some(styles), false) = (self.styles, from_block) {
+            let line_style = styles.line_style(&self.ent.common, &self.by_block);
+            let line_weight = styles.line_weight(&self.ent.common, &self.by_block);
+            let color = styles.color_name(&self.ent.common, &self.by_block);
+            obj.restyle(&|style| {
+                style.line_style = line_style;
+                style.line_weight = line_weight;
+                style.color = color.into();
+            });
+        }
 
-                        dtext.scale(self.scale_fact.x, self.scale_fact.y);
+        Ok(obj)
+    }
 
-                        dtext.x += self.offset.x;
-                        dtext.y -= self.offset.y;
+    //the anonymous block holding the geometry of a dimension, most writers include it
+    fn dimension_block(&self) -> Option<&'a Block> {
+        let base = dimension_base(&self.ent.specific)?;
+        self.blocks
+            .iter()
+            .find(|bl| bl.name == base.block_name)
+            .copied()
+    }
 
-                        Objects::DynamicText(dtext)
-                    },
-                )
-            }
-            EntityType::Ellipse(ellipse) => {
-                let mut ellipse: Ellipse = ellipse.into();
+    //the objects for the entities (and hatches) in a block, with everything set to BYBLOCK
+    //taking on the values of our entity
+    fn expand_block(&self, block: &Block, transform: Transform) -> Objects {
+        let by_block = self
+            .styles
+            .map(|styles| ByBlock {
+                layer: styles.layer_name(&self.ent.common, &self.by_block),
+                line_type: styles.line_type_name(&self.ent.common, &self.by_block),
+                line_weight: styles.line_weight_raw(&self.ent.common, &self.by_block),
+                color: styles.color(&self.ent.common, &self.by_block),
+            })
+            .unwrap_or_default();
 
-                ellipse.scale(self.scale_fact.x, self.scale_fact.y);
-                ellipse.x += self.offset.x;
-                ellipse.y -= self.offset.y;
+        let mut block_path = self.block_path.clone();
+        block_path.push(block.name.clone());
 
-                Ok(Objects::Ellipse(ellipse))
+        //hatches go in first so they end up underneath the rest of the block
+        let hatches = self
+            .hatches
+            .map_or(&[][..], |hatches| hatches.in_block(&block.name))
+            .iter()
+            .filter_map(|hatch| {
+                hatch.build_reported(&transform, self.styles, &by_block, self.report, &block_path)
+            });
+        let entities = block.entities.iter().filter_map(|ent| {
+            let builder = ObjectsBuilder {
+                report: self.report,
+                block_path: block_path.clone(),
+                ..ObjectsBuilder::new(ent, self.spline_step)
             }
-            EntityType::MText(mtext) => {
-                Ok(
-                    //right now the dxf2elmt defaults to making all text Static Text...
-                    //it was requested by the QET devs to add in support for Dynamic text
-                    //which was added, but it defaults to OFF, and QET doesn't pass the parameter
-                    //to enable it...I'm wondering if it makes more sense to default to use dynamic text
-                    //for now I'll set it to use dynamic text, and once I get the CLI flag passing through
-                    //I might change the default parameter to use Dynamic Text
-                    if false {
-                        //how best to pass in the flag for dynamic text or not....should the flag also default to true?
-                        /*let mut text: Text =
-                            (mtext, HexColor::from_u32(ent.common.color_24_bit as u32)).into();
-                            text.x += offset_x;
-                            text.y -= offset_y;
-                        Objects::Text(text)*/
-                        todo!();
-                    } else {
-                        let mut dtext = DTextBuilder::from_mtext(mtext)
-                            .color(HexColor::from_u32(self.ent.common.color_24_bit as u32))
-                            .build();
+            .ellipse_tolerance(self.ellipse_tolerance)
+            .font_metrics(self.font_metrics)
+            .transform(transform)
+            .blocks(self.blocks)
+            .styles(self.styles)
+            .terminals(self.terminals)
+            .element_info(self.element_info)
+            .by_block(by_block.clone());
+            match self.hatches {
+                Some(hatches) => builder.hatches(hatches),
+                None => builder,
+            }
+            .build()
+            .ok()
+        });
+        Objects::Group(hatches.chain(entities).collect())
+    }
 
-                        dtext.scale(self.scale_fact.x, self.scale_fact.y);
+    //texts aren't limited to the named colors like the geometry is
+    fn text_color(&self) -> HexColor {
+        self.styles.map_or(HexColor::BLACK, |styles| {
+            styles.color(&self.ent.common, &self.by_block)
+        })
+    }
 
-                        dtext.x += self.offset.x;
-                        dtext.y -= self.offset.y;
+    fn text_mode(&self) -> TextMode {
+        self.styles.map_or_else(TextMode::default, |styles| {
+            styles.text_mode(&self.ent.common, &self.by_block)
+        })
+    }
 
-                        Objects::DynamicText(dtext)
-                    },
-                )
+    //the entity, when it follows one of the conventions for marking terminals
+    fn terminal(&self) -> Option<Terminal> {
+        let rules = self.terminals?;
+        let layer = self.styles.map_or_else(
+            || self.ent.common.layer.clone(),
+            |styles| styles.layer_name(&self.ent.common, &self.by_block),
+        );
+
+        match &self.ent.specific {
+            EntityType::ModelPoint(point) if rules.is_terminal_layer(&layer) => {
+                Some(Terminal::new(point.location.x, point.location.y, ""))
             }
-            EntityType::Polyline(polyline) => match polyline.__vertices_and_handles.len() {
-                0 | 1 => Err("Error empty Polyline"),
-                2 => {
-                    let mut line = Line::try_from(polyline)?;
+            EntityType::Circle(circle)
+                if rules.is_terminal_layer(&layer)
+                    && circle.radius * self.transform.scale_x() <= rules.max_radius =>
+            {
+                Some(Terminal::new(circle.center.x, circle.center.y, ""))
+            }
+            EntityType::Insert(ins) if rules.is_terminal_block(&ins.name) => {
+                let name = ins
+                    .attributes()
+                    .next()
+                    .map(|att| att.value.clone())
+                    .unwrap_or_default();
+                Some(Terminal::new(ins.location.x, ins.location.y, name))
+            }
+            EntityType::AttributeDefinition(attdef) if rules.is_terminal_tag(&attdef.text_tag) => {
+                Some(Terminal::new(
+                    attdef.location.x,
+                    attdef.location.y,
+                    attdef.value.clone(),
+                ))
+            }
+            _ => None,
+        }
+    }
 
-                    line.scale(self.scale_fact.x, self.scale_fact.x);
+    //the element information a text like %LABEL% or -K? is standing in for
+    fn placeholder_info(&self, text: &str) -> Option<String> {
+        self.element_info
+            .and_then(|map| info::placeholder_info(map, text))
+            .map(String::from)
+    }
 
-                    line.x1 += self.offset.x;
-                    line.y1 -= self.offset.y;
+    #[allow(clippy::too_many_lines)]
+    fn build_entity(&self) -> Result<Objects, ConversionError> {
+        if let Some(term) = self.terminal() {
+            trace!("Found a terminal: {term:?}");
+            return Ok(term.transform(&self.transform));
+        }
 
-                    line.x2 += self.offset.x;
-                    line.y2 -= self.offset.y;
+        match &self.ent.specific {
+            EntityType::Circle(circle) => {
+                let ellipse: Ellipse = circle.into();
+                Ok(ellipse.transform(&self.transform))
+            }
+            EntityType::Line(line) => {
+                let line: Line = line.into();
+                Ok(line.transform(&self.transform))
+            }
+            EntityType::Arc(arc) => {
+                let arc: Arc = arc.into();
+                Ok(arc.transform(&self.transform))
+            }
+            EntityType::Spline(spline) => {
+                let poly: Polygon = (spline, self.spline_step).into();
 
-                    Ok(Objects::Line(line))
+                match poly.coordinates.len() {
+                    0 | 1 => Err(ConversionError::NotEnoughPoints("spline".into())),
+                    //I'll need to improve my understanding of splines and the math here
+                    //to make sure I do this correctly.
+                    //2 => //convert to line
+                    _ => Ok(poly.transform(&self.transform)),
+                }
+            }
+            //a placeholder is always dynamic, the element information is what it's there for
+            EntityType::Text(text) => Ok(
+                match (self.text_mode(), self.placeholder_info(&text.value)) {
+                    (TextMode::Static, None) => {
+                        let mut text: Text = (text, self.text_color()).into();
+                        text.font_metrics = self.font_metrics;
+                        text.transform(&self.transform)
+                    }
+                    (_, info_name) => DTextBuilder::from_text(text)
+                        .info_name(info_name)
+                        .color(self.text_color())
+                        .font_metrics(self.font_metrics)
+                        .build()
+                        .transform(&self.transform),
+                },
+            ),
+            EntityType::Ellipse(ellipse) => Ok(DxfEllipse {
+                ellipse,
+                tolerance: self.ellipse_tolerance,
+            }
+            .to_objects(&self.transform)),
+            EntityType::MText(mtext) => Ok(
+                match (
+                    self.text_mode(),
+                    self.placeholder_info(&normalize_mtext(&mtext.text)),
+                ) {
+                    (TextMode::Static, None) => {
+                        let mut text: Text = (mtext, self.text_color()).into();
+                        text.font_metrics = self.font_metrics;
+                        text.transform(&self.transform)
+                    }
+                    (_, info_name) => DTextBuilder::from_mtext(mtext)
+                        .info_name(info_name)
+                        .color(self.text_color())
+                        .font_metrics(self.font_metrics)
+                        .build()
+                        .transform(&self.transform),
+                },
+            ),
+            EntityType::Polyline(polyline) if polyline.vertices().any(|vtx| vtx.bulge != 0.0) => {
+                let bulged: BulgedPolyline = polyline.into();
+                Ok(Objects::Group(bulged.0).transform(&self.transform))
+            }
+            EntityType::Polyline(polyline) => match polyline.__vertices_and_handles.len() {
+                0 | 1 => Err(ConversionError::NotEnoughPoints("polyline".into())),
+                2 => {
+                    let line = Line::try_from(polyline)?;
+                    Ok(line.transform(&self.transform))
                 }
                 _ => {
-                    if let Ok(mut ellipse) = Ellipse::try_from(polyline) {
-                        ellipse.scale(self.scale_fact.x, self.scale_fact.y);
-
-                        ellipse.x += self.offset.x;
-                        ellipse.y -= self.offset.y;
-
-                        Ok(Objects::Ellipse(ellipse))
+                    if let Ok(ellipse) = Ellipse::try_from(polyline) {
+                        Ok(ellipse.transform(&self.transform))
                     } else {
-                        let mut poly: Polygon = polyline.into();
-
-                        poly.scale(self.scale_fact.x, self.scale_fact.y);
-
-                        for cord in &mut poly.coordinates {
-                            cord.x += self.offset.x;
-                            cord.y -= self.offset.y;
-                        }
-
-                        Ok(Objects::Polygon(poly))
+                        let poly: Polygon = polyline.into();
+                        Ok(poly.transform(&self.transform))
                     }
                 }
             },
+            EntityType::LwPolyline(lwpolyline)
+                if lwpolyline.vertices.iter().any(|vtx| vtx.bulge != 0.0) =>
+            {
+                let bulged: BulgedPolyline = lwpolyline.into();
+                Ok(Objects::Group(bulged.0).transform(&self.transform))
+            }
             EntityType::LwPolyline(lwpolyline) => match lwpolyline.vertices.len() {
-                0 | 1 => Err("Error empty LwPolyline"),
+                0 | 1 => Err(ConversionError::NotEnoughPoints("lwpolyline".into())),
                 2 => {
-                    let mut line = Line::try_from(lwpolyline)?;
-
-                    line.scale(self.scale_fact.x, self.scale_fact.y);
-
-                    line.x1 += self.offset.x;
-                    line.y1 -= self.offset.y;
-
-                    line.x2 += self.offset.x;
-                    line.y2 -= self.offset.y;
-
-                    Ok(Objects::Line(line))
+                    let line = Line::try_from(lwpolyline)?;
+                    Ok(line.transform(&self.transform))
                 }
                 _ => {
-                    if let Ok(mut ellipse) = Ellipse::try_from(lwpolyline) {
-                        ellipse.scale(self.scale_fact.x, self.scale_fact.y);
-
-                        ellipse.x += self.offset.x;
-                        ellipse.y -= self.offset.y;
-
-                        Ok(Objects::Ellipse(ellipse))
+                    if let Ok(ellipse) = Ellipse::try_from(lwpolyline) {
+                        Ok(ellipse.transform(&self.transform))
                     } else {
-                        let mut poly: Polygon = lwpolyline.into();
-
-                        poly.scale(self.scale_fact.x, self.scale_fact.y);
-
-                        for cord in &mut poly.coordinates {
-                            cord.x += self.offset.x;
-                            cord.y -= self.offset.y;
-                        }
-
-                        Ok(Objects::Polygon(poly))
+                        let poly: Polygon = lwpolyline.into();
+                        Ok(poly.transform(&self.transform))
                     }
                 }
             },
             EntityType::Solid(solid) => {
-                let mut poly: Polygon = solid.into();
-
-                poly.scale(self.scale_fact.x, self.scale_fact.y);
-
-                for cord in &mut poly.coordinates {
-                    cord.x += self.offset.x;
-                    cord.y -= self.offset.y;
-                }
-
-                Ok(Objects::Polygon(poly))
+                let poly: Polygon = solid.into();
+                Ok(poly.transform(&self.transform))
             }
             EntityType::Insert(ins) => {
                 //info!("Found an Insert Block: {ins:?}");
                 info!("Found an Insert Block: {}", &ins.name);
                 let Some(block) = self.blocks.iter().find(|bl| bl.name == ins.name) else {
                     error!("Block {} not found", ins.name);
-                    return Err("Block Not Found");
+                    return Err(ConversionError::BlockNotFound(ins.name.clone()));
                 };
                 trace!(
                     "Base Point: x: {} / y: {}",
@@ -760,65 +1017,71 @@ impl<'a> ObjectsBuilder<'a> {
                     block.base_point.y
                 );
 
-                trace!("Creating Group from block {}. Pos(x:{}, y:{}). Offset(x:{}, y:{}). Scale(x:{}, y:{})",
-                    ins.name, ins.location.x, ins.location.y, self.offset.x, self.offset.y, self.scale_fact.x * ins.x_scale_factor,
-                    self.scale_fact.y * ins.y_scale_factor);
-                Ok(Objects::Group(
-                    block
-                        .entities
-                        .iter()
-                        .filter_map(|ent| {
-                            ObjectsBuilder::new(ent, self.spline_step)
-                                .offsets(
-                                    ins.location.x - block.base_point.x,
-                                    ins.location.y - block.base_point.y,
-                                )
-                                .scaling(
-                                    self.scale_fact.x * ins.x_scale_factor,
-                                    self.scale_fact.y * ins.y_scale_factor,
-                                )
-                                .blocks(self.blocks)
-                                .build()
-                                .ok()
-                        })
-                        .collect(),
-                ))
+                //the insert places the block inside of whatever contains the insert
+                //so for nested blocks its transform is applied first, then ours
+                let transform = Transform::insert(ins, &block.base_point).then(&self.transform);
+                trace!("Creating Group from block {}. Pos(x:{}, y:{}). Rotation: {}. Scale(x:{}, y:{}). Transform: {transform:?}",
+                    ins.name, ins.location.x, ins.location.y, ins.rotation, ins.x_scale_factor, ins.y_scale_factor);
+                Ok(self.expand_block(block, transform))
+            }
+            EntityType::RotatedDimension(_)
+            | EntityType::RadialDimension(_)
+            | EntityType::DiameterDimension(_)
+            | EntityType::AngularThreePointDimension(_)
+            | EntityType::OrdinateDimension(_) => {
+                //the geometry in a dimension block is already where it goes in the drawing
+                if let Some(block) = self.dimension_block() {
+                    return Ok(self.expand_block(block, self.transform));
+                }
+
+                let style = match (self.styles, dimension_base(&self.ent.specific)) {
+                    (Some(styles), Some(base)) => styles.dim_style(&base.dimension_style_name),
+                    _ => DimStyle::default(),
+                };
+                Dimension::fallback(
+                    &self.ent.specific,
+                    &style,
+                    self.text_color(),
+                    self.text_mode(),
+                    self.font_metrics,
+                )
+                .map(|dim| Objects::Group(dim.0).transform(&self.transform))
+                .ok_or(ConversionError::UnsupportedDimension)
             }
             EntityType::Leader(leader) => {
                 let ld: Leader = leader.into();
 
                 Ok(Objects::Group(
                     ld.0.into_iter()
-                        .map(|mut ln| {
-                            ln.scale(self.scale_fact.x, self.scale_fact.y);
-
-                            ln.x1 += self.offset.x;
-                            ln.y1 -= self.offset.y;
-
-                            ln.x2 += self.offset.x;
-                            ln.y2 -= self.offset.y;
-
-                            Objects::Line(ln)
-                        })
+                        .map(|ln| ln.transform(&self.transform))
                         .collect(),
                 ))
             }
+            //only there to say what kind of element it is or what it's called, which goes in the
+            //definition. That's only read from the drawing itself, the ones in blocks are drawn
+            EntityType::AttributeDefinition(attrib)
+                if self.block_path.is_empty()
+                    && (kind::is_kind_tag(&attrib.text_tag)
+                        || names::is_name_tag(&attrib.text_tag)) =>
+            {
+                Ok(Objects::Group(Vec::new()))
+            }
             EntityType::AttributeDefinition(attrib) => Ok({
                 //need to look up the proper way to get the color for the Attrib
-                let mut dtext = DTextBuilder::from_attrib(attrib)
-                    .color(HexColor::from_u32(self.ent.common.color_24_bit as u32))
-                    .build();
-
-                dtext.scale(self.scale_fact.x, self.scale_fact.y);
-
-                dtext.x += self.offset.x;
-                dtext.y -= self.offset.y;
-
-                Objects::DynamicText(dtext)
+                let info_name = self
+                    .element_info
+                    .and_then(|map| info::info_for(map, &attrib.text_tag))
+                    .map(String::from);
+                DTextBuilder::from_attrib(attrib)
+                    .info_name(info_name)
+                    .color(self.text_color())
+                    .font_metrics(self.font_metrics)
+                    .build()
+                    .transform(&self.transform)
             }),
             _ => {
                 //dbg!(&self.ent.specific);
-                Err("Need to implement the rest of the entity types")
+                Err(ConversionError::Unsupported)
             }
         }
     }
@@ -833,6 +1096,7 @@ impl From<&Objects> for Either<XMLElement, Vec<XMLElement>> {
             Objects::DynamicText(dtext) => Either::Left(dtext.into()),
             Objects::Text(txt) => Either::Left(txt.into()),
             Objects::Line(line) => Either::Left(line.into()),
+            Objects::Terminal(term) => Either::Left(term.into()),
             Objects::Group(block) => Either::Right(
                 block
                     .iter()
@@ -845,7 +1109,7 @@ impl From<&Objects> for Either<XMLElement, Vec<XMLElement>> {
 }
 
 impl TryFrom<&Objects> for XMLElement {
-    type Error = &'static str; // add better error later
+    type Error = ConversionError;
 
     fn try_from(obj: &Objects) -> Result<Self, Self::Error> {
         match obj {
@@ -855,7 +1119,8 @@ impl TryFrom<&Objects> for XMLElement {
             Objects::DynamicText(dtext) => Ok(dtext.into()),
             Objects::Text(txt) => Ok(txt.into()),
             Objects::Line(line) => Ok(line.into()),
-            Objects::Group(_) => Err("Unsupported"),
+            Objects::Terminal(term) => Ok(term.into()),
+            Objects::Group(_) => Err(ConversionError::Group),
         }
     }
 }
@@ -863,69 +1128,31 @@ impl TryFrom<&Objects> for XMLElement {
 #[derive(Debug)]
 pub struct Description {
     objects: Vec<Objects>,
-}
-
-impl ScaleEntity for Description {
-    fn scale(&mut self, fact_x: f64, fact_y: f64) {
-        self.objects
-            .iter_mut()
-            .for_each(|ob| ob.scale(fact_x, fact_y));
-    }
-
-    fn left_bound(&self) -> f64 {
-        let lb = self.objects.iter().min_by(|ob1, ob2| {
-            ob1.left_bound()
-                .partial_cmp(&ob2.left_bound())
-                .unwrap_or(std::cmp::Ordering::Greater)
-        });
-
-        if let Some(lb) = lb {
-            lb.left_bound()
-        } else {
-            0.0
-        }
-    }
 
-    fn right_bound(&self) -> f64 {
-        let rb = self.objects.iter().max_by(|ob1, ob2| {
-            ob1.right_bound()
-                .partial_cmp(&ob2.right_bound())
-                .unwrap_or(std::cmp::Ordering::Less)
-        });
+    //what happened to each of the entities
+    report: Report,
+}
 
-        if let Some(rb) = rb {
-            rb.left_bound()
-        } else {
-            0.0
-        }
+impl Description {
+    fn transform(&mut self, tf: &Transform) {
+        self.objects = std::mem::take(&mut self.objects)
+            .into_iter()
+            .map(|ob| ob.transform(tf))
+            .collect();
     }
 
-    fn top_bound(&self) -> f64 {
-        let tb = self.objects.iter().min_by(|ob1, ob2| {
-            ob1.top_bound()
-                .partial_cmp(&ob2.top_bound())
-                .unwrap_or(std::cmp::Ordering::Greater)
-        });
-
-        if let Some(tb) = tb {
-            tb.top_bound()
-        } else {
-            0.0
+    fn orient_terminals(&mut self, bbox: &BoundingBox) {
+        for ob in &mut self.objects {
+            ob.orient_terminals(bbox);
         }
     }
+}
 
-    fn bot_bound(&self) -> f64 {
-        let bb = self.objects.iter().max_by(|ob1, ob2| {
-            ob1.bot_bound()
-                .partial_cmp(&ob2.bot_bound())
-                .unwrap_or(std::cmp::Ordering::Less)
-        });
-
-        if let Some(bb) = bb {
-            bb.top_bound()
-        } else {
-            0.0
-        }
+impl Bounds for Description {
+    fn bounding_box(&self) -> BoundingBox {
+        self.objects.iter().fold(BoundingBox::EMPTY, |bbox, ob| {
+            bbox.union(&ob.bounding_box())
+        })
     }
 }
 
@@ -953,85 +1180,95 @@ impl From<&Description> for XMLElement {
         drw.entities().filter_map(|ent| Objects::try_from(ent).ok()).collect();
     }
 }*/
-impl From<(&Drawing, u32)> for Description {
-    fn from((drw, spline_step): (&Drawing, u32)) -> Self {
+//the uuid namespace is only there for a deterministic conversion, see [`element_uuid`]
+impl From<(&Drawing, &Hatches, &ConversionOptions, Option<Uuid>)> for Description {
+    fn from(
+        (drw, hatches, options, uuids): (&Drawing, &Hatches, &ConversionOptions, Option<Uuid>),
+    ) -> Self {
         let _from_drw_span = span!(Level::TRACE, "Converting Drawing to Description");
 
-        Self {
-            objects: drw
-                .entities()
-                .filter_map(|ent| match &ent.specific {
-                    EntityType::Insert(ins) => {
-                        let block = find_block(drw, &ins.name)?;
-                        let blocks: Vec<&Block> = drw.blocks().collect();
-                        trace!(
-                            "Creating Group from block {}. Pos(x:{}, y:{}). Scale(x:{}, y:{})",
-                            ins.name,
-                            ins.location.x,
-                            ins.location.y,
-                            ins.x_scale_factor,
-                            ins.y_scale_factor
-                        );
-                        Some(Objects::Group(
-                            block
-                                .entities
-                                .iter()
-                                .filter_map(|ent| {
-                                    ObjectsBuilder::new(ent, spline_step)
-                                        //very confused here, in one test file if I leave out the ins locations here it puts things in the
-                                        //wrong location, and puts them in the correct location when I add the ins location in.
-                                        //but in another file it's the opposite, not sure why the difference...
-                                        .offsets(ins.location.x, ins.location.y)
-                                        .scaling(ins.x_scale_factor, ins.y_scale_factor)
-                                        .blocks(&blocks)
-                                        .build()
-                                        .ok()
-                                })
-                                .collect(),
-                        ))
-                    }
-                    _ => ObjectsBuilder::new(ent, spline_step).build().ok(),
-                })
-                .collect(),
-        }
-    }
-}
+        let blocks: Vec<&Block> = drw.blocks().collect();
+        let styles = StyleResolver::new(drw, options);
+        let terminals = options.terminals.as_ref().map(|rules| {
+            rules.in_drawing_units(Definition::scale_factor(drw.header.default_drawing_units))
+        });
 
-//probably don't need to worry about this as they won't exist in the dxf...
-/*pub struct Terminal {
-    x: f64,
-    y: f64,
-    uuid: Uuid,
-    name: String,
-    orientation: TermOrient,
-    //type?
-    //  Generic
-    //  Indoor Terminal Block
-    //  External Terminal Block
-}*/
+        //hatches first, so the fills don't cover up anything else
+        let report = RefCell::new(Report::default());
+        if hatches.unread() > 0 {
+            //there's no handle or layer to go with it, the hatches weren't read at all
+            report.borrow_mut().add(
+                &EntityCommon::default(),
+                "Hatch",
+                &[],
+                Outcome::dropped(&ConversionError::BinaryHatches(hatches.unread())),
+            );
+        }
+        let hatches_objs = hatches.in_entities().iter().filter_map(|hatch| {
+            hatch.build_reported(
+                &Transform::identity(),
+                Some(&styles),
+                &ByBlock::default(),
+                Some(&report),
+                &[],
+            )
+        });
 
-#[derive(Debug)]
-pub struct Names {
-    names: Vec<Name>,
-}
+        let objects = hatches_objs
+            .chain(drw.entities().enumerate().filter_map(|(i, ent)| {
+                //inserts are placed by the builder, it works out the transform from the insert
+                //location, base point, scale and rotation, and carries it down into nested blocks
+                let mut obj = ObjectsBuilder::new(ent, options.spline_step)
+                    .ellipse_tolerance(options.ellipse_tolerance)
+                    //what fonts are installed changes from one machine to the next, so
+                    //a deterministic conversion always goes by the number of characters
+                    .font_metrics(options.font_metrics && !options.deterministic)
+                    .blocks(&blocks)
+                    .hatches(hatches)
+                    .styles(Some(&styles))
+                    .terminals(terminals.as_ref())
+                    .element_info(Some(&options.element_info))
+                    .report(&report)
+                    .build()
+                    .ok()?;
+                if let Some(uuids) = uuids {
+                    let entity_uuid = entity_uuid(&uuids, ent, i);
+                    let mut count = 0_u32;
+                    obj.set_uuids(&mut || {
+                        count += 1;
+                        Uuid::new_v5(&entity_uuid, &count.to_be_bytes())
+                    });
+                }
+                Some(obj)
+            }))
+            .collect();
 
-impl From<&Names> for XMLElement {
-    fn from(nme: &Names) -> Self {
-        let mut names_elmt = XMLElement::new("names");
-        for name in &nme.names {
-            let mut nm_elmt = XMLElement::new("name");
-            nm_elmt.add_attribute("lang", &name.lang);
-            nm_elmt.add_text(&name.value);
-            names_elmt.add_child(nm_elmt);
+        Self {
+            objects,
+            report: report.into_inner(),
         }
-        names_elmt
     }
 }
 
-#[derive(Debug)]
-pub struct Name {
-    lang: String, //should this be an enum of language shorts at some point, maybe not worth it
-    value: String,
+/// The uuid of the element when it's converted deterministically, made out of its name
+/// and the contents of the .dxf file. The uuids of its texts and terminals are made out of
+/// this one, so converting the same file again gives back the exact same .elmt
+#[must_use]
+pub fn element_uuid(name: &str, contents: &[u8]) -> Uuid {
+    Uuid::new_v5(
+        &Uuid::new_v5(&Uuid::NAMESPACE_OID, contents),
+        name.as_bytes(),
+    )
+}
+
+//the uuid the ones for the parts of an entity are made out of, it goes by the handle.
+//R12 drawings often don't have handles, those go by where the entity is in the drawing instead
+fn entity_uuid(element_uuid: &Uuid, ent: &Entity, index: usize) -> Uuid {
+    let key = match ent.common.handle.0 {
+        0 => format!("#{index}"),
+        _ => ent.common.handle.as_string(),
+    };
+    Uuid::new_v5(element_uuid, key.as_bytes())
 }
 
 #[derive(Debug)]
@@ -1220,35 +1457,6 @@ impl Display for LineEnd {
     }
 }
 
-#[derive(Debug)]
-enum LinkType {
-    Simple,
-    /*Master,
-    Slave,
-    NextReport,
-    PrevReport,
-    TermBlock,
-    Thumbnail,*/
-}
-
-impl Display for LinkType {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                Self::Simple => "simple",
-                /*Self::Master => "master",
-                Self::Slave => "slave",
-                Self::NextReport => "next_report",
-                Self::PrevReport => "previous_report",
-                Self::TermBlock => "terminal",
-                Self::Thumbnail => "thumbnail",*/
-            }
-        )
-    }
-}
-
 #[derive(Debug)]
 pub struct ElemInfos {
     elem_info: Vec<ElemInfo>,
@@ -1305,6 +1513,38 @@ pub fn two_dec(num: f64) -> f64 {
     (num * 100.0).round() / 100.0
 }
 
+//case insensitive, with * standing in for any number of characters
+pub(crate) fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.to_uppercase();
+    let name = name.to_uppercase();
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        //no * at all
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(pos) => rest = &rest[pos + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+//what comes after the prefix of a tag like KIND_TYPE or NAME_FR, the prefix being
+//case insensitive. None when the tag doesn't start with it or there's nothing after it
+pub(crate) fn strip_tag_prefix<'t>(tag: &'t str, prefix: &str) -> Option<&'t str> {
+    let start = tag.get(..prefix.len())?;
+    let rest = tag.get(prefix.len()..)?;
+    (start.eq_ignore_ascii_case(prefix) && !rest.is_empty()).then_some(rest)
+}
+
 //Should be the relevant Qt5 Code for the font string in Qt5...
 //Might need to look it up for Qt6, since it appears to have changed
 //and add in support for either or?
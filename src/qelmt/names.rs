@@ -0,0 +1,208 @@
+use super::info::drawing_attributes;
+use super::strip_tag_prefix;
+use crate::ConversionOptions;
+use dxf::Drawing;
+use simple_xml_builder::XMLElement;
+use std::collections::BTreeMap;
+
+/// The attribute tags starting with this name the element in a language, `NAME_FR` for French
+pub const NAME_TAG_PREFIX: &str = "NAME_";
+
+/// What the file with the names of the element, next to the .dxf, ends in
+pub const NAMES_FILE_EXTENSION: &str = "names";
+
+#[derive(Debug)]
+pub struct Names {
+    names: Vec<Name>,
+}
+
+impl From<&Names> for XMLElement {
+    fn from(nme: &Names) -> Self {
+        let mut names_elmt = XMLElement::new("names");
+        for name in &nme.names {
+            let mut nm_elmt = XMLElement::new("name");
+            nm_elmt.add_attribute("lang", &name.lang);
+            nm_elmt.add_text(&name.value);
+            names_elmt.add_child(nm_elmt);
+        }
+        names_elmt
+    }
+}
+
+#[derive(Debug)]
+pub struct Name {
+    //the language code QET uses, en, fr, pt_BR... there's too many of them to keep in an enum
+    lang: String,
+    value: String,
+}
+
+//the language a NAME_* tag names the element in
+fn name_lang(tag: &str) -> Option<&str> {
+    strip_tag_prefix(tag, NAME_TAG_PREFIX)
+}
+
+//QET writes the languages as xx or xx_YY (pt_BR, nl_BE), whatever case they're given in
+fn normalize_lang(lang: &str) -> String {
+    let mut parts = lang.split(['_', '-']);
+    let language = parts.next().unwrap_or_default().to_lowercase();
+    parts.fold(language, |code, region| code + "_" + &region.to_uppercase())
+}
+
+/// Whether the attribute is only there to name the element, and not something to draw
+pub(crate) fn is_name_tag(tag: &str) -> bool {
+    name_lang(tag).is_some()
+}
+
+/// One name of the element, as LANG=NAME. The name can be in quotes
+///
+/// # Errors
+///
+/// If there's no `=` or the language isn't made of letters, `_` and `-`
+pub fn parse_name(arg: &str) -> Result<(String, String), String> {
+    let (lang, name) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("expected LANG=NAME, got '{arg}'"))?;
+    let lang = lang.trim();
+    if lang.is_empty()
+        || !lang
+            .chars()
+            .all(|ch| ch.is_ascii_alphabetic() || ch == '_' || ch == '-')
+    {
+        return Err(format!("'{lang}' isn't a language code like en or fr"));
+    }
+
+    let name = name.trim();
+    let name = name
+        .strip_prefix('"')
+        .and_then(|name| name.strip_suffix('"'))
+        .unwrap_or(name);
+    Ok((normalize_lang(lang), name.to_string()))
+}
+
+/// The names in a .names file, one LANG=NAME a line. Blank lines and the ones starting with # are skipped
+///
+/// # Errors
+///
+/// If one of the lines isn't a name, see [`parse_name`]
+pub fn parse_names_file(contents: &str) -> Result<Vec<(String, String)>, String> {
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(i, line)| parse_name(line).map_err(|err| format!("line {}: {err}", i + 1)))
+        .collect()
+}
+
+/// The names of the element: the `NAME_*` attributes in the drawing, overridden by the ones
+/// in the options. The file name is the English name, unless there's one already
+pub(crate) fn element_names(
+    file_name: String,
+    drw: &Drawing,
+    options: &ConversionOptions,
+) -> Names {
+    let mut names = BTreeMap::new();
+    names.insert("en".to_string(), file_name);
+
+    let from_drawing = drawing_attributes(drw)
+        .into_iter()
+        .filter_map(|(tag, value)| Some((normalize_lang(name_lang(&tag)?), value)));
+    let from_options = options
+        .names
+        .iter()
+        .map(|(lang, value)| (normalize_lang(lang), value.clone()));
+    for (lang, value) in from_drawing.chain(from_options) {
+        if !value.trim().is_empty() {
+            names.insert(lang, value.trim().to_string());
+        }
+    }
+
+    Names {
+        names: names
+            .into_iter()
+            .map(|(lang, value)| Name { lang, value })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{element_names, normalize_lang, parse_names_file, Names};
+    use crate::ConversionOptions;
+    use dxf::entities::{AttributeDefinition, Entity, EntityType};
+    use dxf::Drawing;
+
+    fn drawing(attdefs: &[(&str, &str)]) -> Drawing {
+        let mut drw = Drawing::new();
+        for (tag, value) in attdefs {
+            drw.add_entity(Entity::new(EntityType::AttributeDefinition(
+                AttributeDefinition {
+                    text_tag: (*tag).to_string(),
+                    value: (*value).to_string(),
+                    ..Default::default()
+                },
+            )));
+        }
+        drw
+    }
+
+    fn pairs(names: &Names) -> Vec<(&str, &str)> {
+        names
+            .names
+            .iter()
+            .map(|name| (name.lang.as_str(), name.value.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn languages_are_normalized() {
+        assert_eq!(normalize_lang("pt-br"), "pt_BR");
+        assert_eq!(normalize_lang("NL_be"), "nl_BE");
+        assert_eq!(normalize_lang("FR"), "fr");
+    }
+
+    #[test]
+    fn names_file() {
+        let names = parse_names_file("# the names\nen = Relay\n\npt-BR=\"Relé\"\n").unwrap();
+        assert_eq!(
+            names,
+            [
+                ("en".to_string(), "Relay".to_string()),
+                ("pt_BR".to_string(), "Relé".to_string())
+            ]
+        );
+
+        let err = parse_names_file("en=Relay\nRelay\n").unwrap_err();
+        assert!(err.starts_with("line 2:"), "{err}");
+    }
+
+    #[test]
+    fn file_name_then_attributes_then_names_file_then_cli() {
+        let drw = drawing(&[
+            ("NAME_EN", "Drawing relay"),
+            ("NAME_FR", "Relais du dessin"),
+            ("NAME_DE", "Relais der Zeichnung"),
+        ]);
+        //the .names file goes in before the names from the command line, like convert_dxf_file does
+        let names_file = parse_names_file("fr=Relais du fichier\nde=Relais der Datei\n").unwrap();
+        let options = ConversionOptions {
+            names: names_file
+                .into_iter()
+                .chain([("de".to_string(), "Relais".to_string())])
+                .collect(),
+            ..Default::default()
+        };
+
+        let names = element_names("relay".to_string(), &drw, &options);
+        assert_eq!(
+            pairs(&names),
+            [
+                ("de", "Relais"),
+                ("en", "Drawing relay"),
+                ("fr", "Relais du fichier")
+            ]
+        );
+
+        let names = element_names("relay".to_string(), &Drawing::new(), &options);
+        assert_eq!(pairs(&names)[1], ("en", "relay"));
+    }
+}
@@ -1,8 +1,19 @@
-use super::{two_dec, ScaleEntity};
+use super::raster::{polyline, Canvas, Paint};
+use super::style::Style;
+use super::svg::{add_style, PatternFills, ToSvg};
+use super::{two_dec, Arc, BoundingBox, Bounds, Objects, Transform, TransformEntity};
 use dxf::entities::{LwPolyline, Polyline, Solid, Spline};
 use simple_xml_builder::XMLElement;
 use std::ops::{Add, Mul};
 
+//QET writes the start and span of an arc out as whole degrees, so a very shallow
+//bulge turned into an arc can end up noticeably off from the polyline it came from.
+//Below this sweep it's more accurate to just tessellate the bulge into the polygon.
+const MIN_ARC_SWEEP_DEG: f64 = 10.0;
+
+//The step used when tessellating those shallow bulges
+const BULGE_STEP_DEG: f64 = 2.0;
+
 //wait Why do I have a coordinate AND a Point struct, that are
 //essentially the same. It's been a couple of months, but I'm not
 //seeing why I would have done this....almost makes me wondering
@@ -44,9 +55,185 @@ impl Add for Point {
     }
 }
 
+/// A single polyline segment with a bulge. The bulge is the tangent of 1/4 of the
+/// included angle of the arc, negative when the arc goes clockwise from `start` to `end`.
+/// All the values are in dxf coordinates (y up), not yet flipped for QET.
+#[derive(Copy, Clone, Debug)]
+pub struct Bulge {
+    pub start: Point,
+    pub end: Point,
+    pub bulge: f64,
+}
+
+impl Bulge {
+    fn chord(&self) -> f64 {
+        ((self.end.x - self.start.x).powi(2) + (self.end.y - self.start.y).powi(2)).sqrt()
+    }
+
+    /// Included angle of the arc in radians, always positive
+    pub fn sweep(&self) -> f64 {
+        4.0 * self.bulge.atan().abs()
+    }
+
+    pub fn radius(&self) -> f64 {
+        self.chord() / (2.0 * (self.sweep() / 2.0).sin())
+    }
+
+    pub fn center(&self) -> Point {
+        //the center sits on the perpendicular bisector of the chord, to the left
+        //of the direction of travel for a counter clockwise (positive) bulge
+        let mid_x = (self.start.x + self.end.x) / 2.0;
+        let mid_y = (self.start.y + self.end.y) / 2.0;
+        let dist = (1.0 - self.bulge.powi(2)) / (4.0 * self.bulge);
+        Point::new(
+            mid_x - (self.end.y - self.start.y) * dist,
+            mid_y + (self.end.x - self.start.x) * dist,
+        )
+    }
+
+    /// Angle in degrees, counter clockwise from the x axis, the arc starts at when
+    /// drawing it counter clockwise. This is the form both dxf and QET use for arcs
+    pub fn start_angle(&self) -> f64 {
+        let center = self.center();
+        let from = if self.bulge > 0.0 {
+            self.start
+        } else {
+            self.end
+        };
+        (from.y - center.y)
+            .atan2(from.x - center.x)
+            .to_degrees()
+            .rem_euclid(360.0)
+    }
+
+    /// Points along the arc, excluding the start and end points themselves
+    pub(crate) fn inner_points(&self) -> Vec<Point> {
+        let center = self.center();
+        let radius = self.radius();
+        let sweep = self.sweep().to_degrees();
+        let steps = (sweep / BULGE_STEP_DEG).ceil().max(1.0) as usize;
+
+        //walk from the actual start vertex, so the direction depends on the bulge sign
+        let from = (self.start.y - center.y).atan2(self.start.x - center.x);
+        let step = self.sweep() / steps as f64 * self.bulge.signum();
+        (1..steps)
+            .map(|i| {
+                let ang = from + step * i as f64;
+                Point::new(center.x + radius * ang.cos(), center.y + radius * ang.sin())
+            })
+            .collect()
+    }
+}
+
+/// A polyline containing bulged segments. The straight runs are kept as polygons and
+/// the bulges are split out into arcs, so the outline keeps its shape.
+pub(crate) struct BulgedPolyline(pub Vec<Objects>);
+
+impl BulgedPolyline {
+    fn from_vertices(vertices: &[(Point, f64)], closed: bool, style: &Style) -> Self {
+        enum Piece {
+            Run(Vec<Coordinate>),
+            Arc(Arc),
+        }
+
+        let to_coord = |pt: Point| Coordinate { x: pt.x, y: -pt.y };
+        let seg_count = if closed {
+            vertices.len()
+        } else {
+            vertices.len().saturating_sub(1)
+        };
+
+        let mut pieces = Vec::new();
+        let mut run = vertices
+            .first()
+            .map(|(pt, _)| vec![to_coord(*pt)])
+            .unwrap_or_default();
+        for i in 0..seg_count {
+            let (start, bulge) = vertices[i];
+            let (end, _) = vertices[(i + 1) % vertices.len()];
+            let seg = Bulge { start, end, bulge };
+
+            if bulge == 0.0 || seg.chord() <= f64::EPSILON {
+                run.push(to_coord(end));
+            } else if seg.sweep().to_degrees() < MIN_ARC_SWEEP_DEG {
+                run.extend(seg.inner_points().into_iter().map(to_coord));
+                run.push(to_coord(end));
+            } else {
+                pieces.push(Piece::Run(std::mem::take(&mut run)));
+                pieces.push(Piece::Arc((&seg, style).into()));
+                run.push(to_coord(end));
+            }
+        }
+        pieces.push(Piece::Run(run));
+        let has_arcs = pieces.iter().any(|piece| matches!(piece, Piece::Arc(_)));
+
+        //When closed the last run ends on the first vertex, so join it up with the first
+        //run instead of leaving a seam at the first vertex
+        if closed && pieces.len() > 1 {
+            if let (Some(Piece::Run(mut last)), Some(Piece::Run(first))) =
+                (pieces.pop(), pieces.first_mut())
+            {
+                last.extend(first.drain(..).skip(1));
+                *first = last;
+            }
+        }
+
+        BulgedPolyline(
+            pieces
+                .into_iter()
+                .filter_map(|piece| match piece {
+                    Piece::Arc(arc) => Some(Objects::Arc(arc)),
+                    Piece::Run(coordinates) if coordinates.len() > 1 => {
+                        Some(Objects::Polygon(Polygon {
+                            coordinates,
+                            closed: false,
+                            //in the original code antialias is always set to false...I'm guessing for performance
+                            //reasons...I'm trying to think if there is a time we might want to turn it on?
+                            antialias: false,
+                            style: style.clone(),
+                        }))
+                    }
+                    Piece::Run(_) => None,
+                })
+                .map(|obj| match obj {
+                    //if every bulge ended up tessellated we're back to a single polygon
+                    //and the outline can be closed normally
+                    Objects::Polygon(mut poly) if closed && !has_arcs => {
+                        poly.closed = true;
+                        poly.coordinates.pop();
+                        Objects::Polygon(poly)
+                    }
+                    obj => obj,
+                })
+                .collect(),
+        )
+    }
+}
+
+impl From<&LwPolyline> for BulgedPolyline {
+    fn from(poly: &LwPolyline) -> Self {
+        let vertices: Vec<(Point, f64)> = poly
+            .vertices
+            .iter()
+            .map(|vtx| (Point::new(vtx.x, vtx.y), vtx.bulge))
+            .collect();
+        Self::from_vertices(&vertices, poly.is_closed(), &Style::default())
+    }
+}
+
+impl From<&Polyline> for BulgedPolyline {
+    fn from(poly: &Polyline) -> Self {
+        let vertices: Vec<(Point, f64)> = poly
+            .vertices()
+            .map(|vtx| (Point::new(vtx.location.x, vtx.location.y), vtx.bulge))
+            .collect();
+        Self::from_vertices(&vertices, poly.is_closed(), &Style::default())
+    }
+}
+
 #[derive(Debug)]
 pub struct Polygon {
-    style: String,
+    pub(crate) style: Style,
     antialias: bool,
     pub coordinates: Vec<Coordinate>,
     closed: bool,
@@ -67,12 +254,7 @@ impl From<&Polyline> for Polygon {
             //in the original code antialias is always set to false...I'm guessing for performance
             //reasons...I'm trying to think if there is a time we might want to turn it on?
             antialias: false,
-            style: if poly.thickness > 0.1 {
-                "line-style:normal;line-weight:normal;filling:none;color:black"
-            } else {
-                "line-style:normal;line-weight:thin;filling:none;color:black"
-            }
-            .into(),
+            style: Style::default(),
         }
     }
 }
@@ -92,12 +274,7 @@ impl From<&LwPolyline> for Polygon {
             //in the original code antialias is always set to false...I'm guessing for performance
             //reasons...I'm trying to think if there is a time we might want to turn it on?
             antialias: false,
-            style: if poly.thickness > 0.1 {
-                "line-style:normal;line-weight:normal;filling:none;color:black"
-            } else {
-                "line-style:normal;line-weight:thin;filling:none;color:black"
-            }
-            .into(),
+            style: Style::default(),
         }
     }
 }
@@ -154,7 +331,7 @@ impl From<(&Spline, u32)> for Polygon {
             //in the original code antialias is always set to false...I'm guessing for performance
             //reasons...I'm trying to think if there is a time we might want to turn it on?
             antialias: false,
-            style: "line-style:normal;line-weight:thin;filling:none;color:black".into(),
+            style: Style::default(),
         }
     }
 }
@@ -184,12 +361,7 @@ impl From<&Solid> for Polygon {
             //in the original code antialias is always set to false...I'm guessing for performance
             //reasons...I'm trying to think if there is a time we might want to turn it on?
             antialias: false,
-            style: if solid.thickness > 0.5 {
-                "line-style:normal;line-weight:normal;filling:none;color:black"
-            } else {
-                "line-style:normal;line-weight:thin;filling:none;color:black"
-            }
-            .into(),
+            style: Style::default(),
         }
     }
 }
@@ -214,69 +386,123 @@ impl From<&Polygon> for XMLElement {
     }
 }
 
-impl ScaleEntity for Polygon {
-    fn scale(&mut self, fact_x: f64, fact_y: f64) {
-        self.coordinates.iter_mut().for_each(|coord| {
-            coord.x *= fact_x;
-            coord.y *= fact_y;
-        });
+impl Polygon {
+    //for the shapes that can't be represented natively anymore after
+    //a transform, and end up being tessellated into a polygon
+    pub(crate) fn new(coordinates: Vec<Coordinate>, closed: bool, style: Style) -> Self {
+        Polygon {
+            style,
+            //in the original code antialias is always set to false...I'm guessing for performance
+            //reasons...I'm trying to think if there is a time we might want to turn it on?
+            antialias: false,
+            coordinates,
+            closed,
+        }
     }
+}
 
-    fn left_bound(&self) -> f64 {
-        let min_coord = self.coordinates.iter().min_by(|c1, c2| {
-            //if we get a None for the compare, then just returns Greater which will ignore it
-            //for finding the minimum
-            c1.x.partial_cmp(&c2.x)
-                .unwrap_or(std::cmp::Ordering::Greater)
-        });
-
-        if let Some(min_coord) = min_coord {
-            min_coord.x
-        } else {
-            0.0
+impl TransformEntity for Polygon {
+    fn transform(mut self, tf: &Transform) -> Objects {
+        for coord in &mut self.coordinates {
+            (coord.x, coord.y) = tf.apply_qet(coord.x, coord.y);
         }
+        Objects::Polygon(self)
+    }
+}
+
+impl Bounds for Polygon {
+    fn bounding_box(&self) -> BoundingBox {
+        BoundingBox::from_points(self.coordinates.iter().map(|coord| (coord.x, coord.y)))
     }
+}
 
-    fn right_bound(&self) -> f64 {
-        let max_coord = self.coordinates.iter().max_by(|c1, c2| {
-            //if we get a None for the compare, then just returns Less which will ignore it
-            //for finding the maximum
-            c1.x.partial_cmp(&c2.x).unwrap_or(std::cmp::Ordering::Less)
-        });
+impl ToSvg for Polygon {
+    fn to_svg(&self, fills: &mut PatternFills) -> XMLElement {
+        let mut poly_svg = XMLElement::new(if self.closed { "polygon" } else { "polyline" });
+        let points: Vec<String> = self
+            .coordinates
+            .iter()
+            .map(|coord| format!("{},{}", two_dec(coord.x), two_dec(coord.y)))
+            .collect();
+        poly_svg.add_attribute("points", points.join(" "));
+        add_style(&mut poly_svg, &self.style, fills);
+        poly_svg
+    }
+}
 
-        if let Some(max_coord) = max_coord {
-            max_coord.x
-        } else {
-            0.0
-        }
+impl Paint for Polygon {
+    fn paint(&self, canvas: &mut Canvas) {
+        let points = self.coordinates.iter().map(|coord| (coord.x, coord.y));
+        canvas.shape(polyline(points, self.closed), &self.style);
     }
+}
 
-    fn top_bound(&self) -> f64 {
-        let min_coord = self.coordinates.iter().min_by(|c1, c2| {
-            //if we get a None for the compare, then just returns Greater which will ignore it
-            //for finding the minimum
-            c1.y.partial_cmp(&c2.y)
-                .unwrap_or(std::cmp::Ordering::Greater)
-        });
+#[cfg(test)]
+mod tests {
+    use super::{Bulge, Point};
 
-        if let Some(min_coord) = min_coord {
-            min_coord.y
-        } else {
-            0.0
-        }
+    //a quarter circle has a bulge of tan(90°/4)
+    const QUARTER: f64 = 0.414_213_562_373_095;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
     }
 
-    fn bot_bound(&self) -> f64 {
-        let max_coord = self.coordinates.iter().max_by(|c1, c2| {
-            //if we get a None for the compare, then just returns Less which will ignore it
-            //for finding the maximum
-            c1.y.partial_cmp(&c2.y).unwrap_or(std::cmp::Ordering::Less)
-        });
+    #[test]
+    fn positive_bulge_goes_counter_clockwise() {
+        let bulge = Bulge {
+            start: Point::new(1.0, 0.0),
+            end: Point::new(0.0, 1.0),
+            bulge: QUARTER,
+        };
+        let center = bulge.center();
+        assert!(close(center.x, 0.0) && close(center.y, 0.0));
+        assert!(close(bulge.radius(), 1.0));
+        assert!(close(bulge.sweep().to_degrees(), 90.0));
+        assert!(close(bulge.start_angle(), 0.0));
 
-        if let Some(max_coord) = max_coord {
-            max_coord.y
-        } else {
-            0.0
-        }
+        //out of (1, 0) counter clockwise, so up and to the left
+        let first = bulge.inner_points()[0];
+        assert!(first.x < 1.0 && first.y > 0.0);
+    }
+
+    #[test]
+    fn negative_bulge_goes_clockwise() {
+        let bulge = Bulge {
+            start: Point::new(0.0, 1.0),
+            end: Point::new(1.0, 0.0),
+            bulge: -QUARTER,
+        };
+        let center = bulge.center();
+        assert!(close(center.x, 0.0) && close(center.y, 0.0));
+        assert!(close(bulge.radius(), 1.0));
+
+        //the arc is written out counter clockwise, so it starts from the end vertex
+        assert!(close(bulge.start_angle(), 0.0));
+
+        //out of (0, 1) clockwise, so down and to the right
+        let first = bulge.inner_points()[0];
+        assert!(first.x > 0.0 && first.y < 1.0);
+    }
+
+    #[test]
+    fn bulge_sign_picks_the_side_of_the_chord() {
+        let (start, end) = (Point::new(0.0, 0.0), Point::new(2.0, 0.0));
+        let ccw = Bulge {
+            start,
+            end,
+            bulge: QUARTER,
+        };
+        let cw = Bulge {
+            start,
+            end,
+            bulge: -QUARTER,
+        };
+
+        //counter clockwise going along +x the arc bows out below the chord, clockwise above it
+        assert!(ccw.center().y > 0.0);
+        assert!(ccw.inner_points().iter().all(|pt| pt.y < 0.0));
+        assert!(cw.center().y < 0.0);
+        assert!(cw.inner_points().iter().all(|pt| pt.y > 0.0));
     }
 }
@@ -0,0 +1,347 @@
+use super::color::named_color;
+use super::style::Style;
+use super::svg::PATTERN_SIZE;
+use super::{FontInfo, Objects};
+use hex_color::HexColor;
+use tiny_skia::{
+    FillRule, LineCap, Mask, Paint as SkPaint, Path, PathBuilder, Pixmap, Stroke, StrokeDash,
+    Transform,
+};
+
+//Drawing the element into a PNG on the CPU, for thumbnails. Same as the SVG preview
+//everything is drawn in QET coordinates, the canvas takes care of scaling them to the DPI
+
+/// Draws a part of the element onto the canvas
+pub(crate) trait Paint {
+    fn paint(&self, canvas: &mut Canvas);
+}
+
+pub(crate) struct Canvas {
+    pixmap: Pixmap,
+
+    //QET coordinates to pixels
+    transform: Transform,
+}
+
+//QET draws 1mm as 2 of its units
+const MM_PER_UNIT: f64 = 0.5;
+
+impl Canvas {
+    /// A white canvas for the area of the element starting at (left, top), `dpi` sets
+    /// how many pixels each of the QET units end up as. None if it's got no area
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    pub fn new(left: i64, top: i64, width: i64, height: i64, dpi: f64) -> Option<Self> {
+        let scale = dpi * MM_PER_UNIT / 25.4;
+        let px = |units: i64| (units as f64 * scale).ceil().max(1.0) as u32;
+        let mut pixmap = Pixmap::new(px(width), px(height))?;
+        pixmap.fill(tiny_skia::Color::WHITE);
+
+        let scale = scale as f32;
+        Some(Self {
+            pixmap,
+            transform: Transform::from_scale(scale, scale).pre_translate(-left as f32, -top as f32),
+        })
+    }
+
+    pub fn into_png(self) -> Option<Vec<u8>> {
+        self.pixmap.encode_png().ok()
+    }
+
+    /// Fills and outlines the path, according to the QET style
+    pub fn shape(&mut self, path: Option<Path>, style: &Style) {
+        let Some(path) = path else {
+            return;
+        };
+
+        match style.filling.as_str() {
+            "none" => {}
+            "hor" | "ver" | "bdiag" | "fdiag" => self.pattern(&path, &style.filling, &style.color),
+            filling => {
+                self.pixmap.fill_path(
+                    &path,
+                    &paint(named_color(filling)),
+                    FillRule::Winding,
+                    self.transform,
+                    None,
+                );
+            }
+        }
+
+        self.stroke(&path, style);
+    }
+
+    /// Only outlines the path, whatever the filling of the style is
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn stroke(&mut self, path: &Path, style: &Style) {
+        let Some(width) = style.line_weight.width() else {
+            return;
+        };
+
+        let dashes: Vec<f32> = style
+            .line_style
+            .dashes()
+            .iter()
+            .map(|dash| (dash * width.max(1.0)) as f32)
+            .collect();
+        let stroke = Stroke {
+            width: width as f32,
+            line_cap: LineCap::Butt,
+            dash: StrokeDash::new(dashes, 0.0),
+            ..Default::default()
+        };
+        self.pixmap.stroke_path(
+            path,
+            &paint(named_color(&style.color)),
+            &stroke,
+            self.transform,
+            None,
+        );
+    }
+
+    //the QET pattern fillings are evenly spaced lines in one direction, drawn across
+    //the box around the shape and then cut down to the shape itself
+    #[allow(clippy::cast_possible_truncation)]
+    fn pattern(&mut self, path: &Path, filling: &str, color: &str) {
+        let Some(mut mask) = Mask::new(self.pixmap.width(), self.pixmap.height()) else {
+            return;
+        };
+        mask.fill_path(path, FillRule::Winding, true, self.transform);
+
+        let bounds = path.bounds();
+        let (left, top) = (f64::from(bounds.left()), f64::from(bounds.top()));
+        let (right, bottom) = (f64::from(bounds.right()), f64::from(bounds.bottom()));
+        let size = (right - left) + (bottom - top);
+
+        let mut pb = PathBuilder::new();
+        let mut offset = 0.0;
+        while offset <= size {
+            let (from, to) = match filling {
+                "hor" => ((left, top + offset), (right, top + offset)),
+                "ver" => ((left + offset, top), (left + offset, bottom)),
+                //going up to the right, y is down
+                "bdiag" => (
+                    (left + offset - size, bottom),
+                    (left + offset, bottom - size),
+                ),
+                _ => ((left + offset - size, top), (left + offset, top + size)),
+            };
+            pb.move_to(from.0 as f32, from.1 as f32);
+            pb.line_to(to.0 as f32, to.1 as f32);
+            offset += PATTERN_SIZE;
+        }
+
+        if let Some(lines) = pb.finish() {
+            let stroke = Stroke {
+                width: 0.5,
+                ..Default::default()
+            };
+            self.pixmap.stroke_path(
+                &lines,
+                &paint(named_color(color)),
+                &stroke,
+                self.transform,
+                Some(&mask),
+            );
+        }
+    }
+
+    /// Draws the glyphs of the text with the font. The first line sits on `baseline`,
+    /// `anchor` is how far along the width of the text x is (0 for the start, 1 for
+    /// the end), and it's all rotated clockwise around `pivot`
+    #[cfg(not(target_arch = "wasm32"))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn text(
+        &mut self,
+        text: &str,
+        font: &FontInfo,
+        (x, baseline): (f64, f64),
+        anchor: f64,
+        rotation: f64,
+        pivot: (f64, f64),
+        color: HexColor,
+    ) {
+        use parley::layout::{Alignment, PositionedLayoutItem};
+        use skrifa::outline::DrawSettings;
+        use skrifa::prelude::{LocationRef, Size};
+        use skrifa::{FontRef, GlyphId, MetadataProvider};
+
+        let glyphs = super::metrics::with_layout(text, font, |layout| {
+            let alignment = match anchor {
+                a if a <= 0.0 => Alignment::Start,
+                a if a >= 1.0 => Alignment::End,
+                _ => Alignment::Middle,
+            };
+            layout.align(None, alignment);
+
+            let first_baseline = layout
+                .lines()
+                .next()
+                .map_or(0.0, |line| f64::from(line.metrics().baseline));
+            let mut pen = GlyphPen {
+                pb: PathBuilder::new(),
+                x: x - anchor * f64::from(layout.width()),
+                y: baseline - first_baseline,
+            };
+
+            for line in layout.lines() {
+                for item in line.items() {
+                    let PositionedLayoutItem::GlyphRun(glyph_run) = item else {
+                        continue;
+                    };
+                    let run = glyph_run.run();
+                    let Ok(font_ref) =
+                        FontRef::from_index(run.font().data.as_ref(), run.font().index)
+                    else {
+                        continue;
+                    };
+                    let outlines = font_ref.outline_glyphs();
+                    let size = Size::new(run.font_size());
+
+                    for glyph in glyph_run.positioned_glyphs() {
+                        let Some(outline) = outlines.get(GlyphId::new(u32::from(glyph.id))) else {
+                            continue;
+                        };
+                        let mut glyph_pen = pen.at(f64::from(glyph.x), f64::from(glyph.y));
+                        //a glyph that can't be drawn is just left out
+                        let _ = outline.draw(
+                            DrawSettings::unhinted(size, LocationRef::default()),
+                            &mut glyph_pen,
+                        );
+                    }
+                }
+            }
+            pen.pb.finish()
+        });
+
+        if let Some(Some(glyphs)) = glyphs {
+            #[allow(clippy::cast_possible_truncation)]
+            let transform = self.transform.pre_concat(Transform::from_rotate_at(
+                rotation as f32,
+                pivot.0 as f32,
+                pivot.1 as f32,
+            ));
+            self.pixmap
+                .fill_path(&glyphs, &paint(color), FillRule::Winding, transform, None);
+        }
+    }
+
+    //There's no way to get at the system fonts from the browser, so no text
+    #[cfg(target_arch = "wasm32")]
+    #[allow(clippy::too_many_arguments, clippy::unused_self)]
+    pub fn text(
+        &mut self,
+        _text: &str,
+        _font: &FontInfo,
+        _position: (f64, f64),
+        _anchor: f64,
+        _rotation: f64,
+        _pivot: (f64, f64),
+        _color: HexColor,
+    ) {
+    }
+}
+
+fn paint(color: HexColor) -> SkPaint<'static> {
+    let mut paint = SkPaint::default();
+    paint.set_color_rgba8(color.r, color.g, color.b, 255);
+    paint.anti_alias = true;
+    paint
+}
+
+//collects the glyph outlines into one path, the outlines have y going up
+#[cfg(not(target_arch = "wasm32"))]
+struct GlyphPen {
+    pb: PathBuilder,
+    x: f64,
+    y: f64,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl GlyphPen {
+    fn at(&mut self, x: f64, y: f64) -> PlacedGlyphPen<'_> {
+        #[allow(clippy::cast_possible_truncation)]
+        PlacedGlyphPen {
+            x: (self.x + x) as f32,
+            y: (self.y + y) as f32,
+            pb: &mut self.pb,
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+struct PlacedGlyphPen<'a> {
+    pb: &'a mut PathBuilder,
+    x: f32,
+    y: f32,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl skrifa::outline::OutlinePen for PlacedGlyphPen<'_> {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.pb.move_to(self.x + x, self.y - y);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.pb.line_to(self.x + x, self.y - y);
+    }
+
+    fn quad_to(&mut self, cx0: f32, cy0: f32, x: f32, y: f32) {
+        self.pb
+            .quad_to(self.x + cx0, self.y - cy0, self.x + x, self.y - y);
+    }
+
+    fn curve_to(&mut self, cx0: f32, cy0: f32, cx1: f32, cy1: f32, x: f32, y: f32) {
+        self.pb.cubic_to(
+            self.x + cx0,
+            self.y - cy0,
+            self.x + cx1,
+            self.y - cy1,
+            self.x + x,
+            self.y - y,
+        );
+    }
+
+    fn close(&mut self) {
+        self.pb.close();
+    }
+}
+
+impl Paint for Objects {
+    fn paint(&self, canvas: &mut Canvas) {
+        match self {
+            Objects::Arc(arc) => arc.paint(canvas),
+            Objects::Ellipse(ell) => ell.paint(canvas),
+            Objects::Polygon(poly) => poly.paint(canvas),
+            Objects::DynamicText(dtext) => dtext.paint(canvas),
+            Objects::Text(txt) => txt.paint(canvas),
+            Objects::Line(line) => line.paint(canvas),
+            Objects::Terminal(term) => term.paint(canvas),
+            Objects::Group(group) => {
+                for obj in group {
+                    obj.paint(canvas);
+                }
+            }
+        }
+    }
+}
+
+/// A path through the points, None if there aren't enough of them to draw anything
+#[allow(clippy::cast_possible_truncation)]
+pub(crate) fn polyline(points: impl IntoIterator<Item = (f64, f64)>, closed: bool) -> Option<Path> {
+    let mut pb = PathBuilder::new();
+    for (i, (x, y)) in points.into_iter().enumerate() {
+        if i == 0 {
+            pb.move_to(x as f32, y as f32);
+        } else {
+            pb.line_to(x as f32, y as f32);
+        }
+    }
+    if closed {
+        pb.close();
+    }
+    pb.finish()
+}
@@ -0,0 +1,179 @@
+use super::error::{ConversionError, EntityError};
+use super::Objects;
+use dxf::entities::EntityCommon;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+/// What happened to every entity in the drawing, including the ones inside of blocks
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Report {
+    pub entities: Vec<EntityReport>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntityReport {
+    pub handle: String,
+    pub layer: String,
+    pub entity_type: String,
+
+    /// The blocks the entity is in, from the outermost one in. Empty for the entities of the drawing itself
+    pub block_path: Vec<String>,
+
+    #[serde(flatten)]
+    pub outcome: Outcome,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum Outcome {
+    /// Turned into the QET parts, with how many of each
+    Converted { parts: BTreeMap<String, usize> },
+
+    /// Turned into the QET parts, but they only come close to what's in the drawing
+    Approximated {
+        parts: BTreeMap<String, usize>,
+        reason: String,
+    },
+
+    /// An insert (or a dimension) whose block was converted in its place,
+    /// the entities of the block have their own entries
+    Expanded { block: String },
+
+    /// Left out of the element
+    Dropped {
+        reason: ConversionError,
+        message: String,
+    },
+}
+
+/// How many of the entities ended up with each outcome
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Summary {
+    pub converted: usize,
+    pub approximated: usize,
+    pub expanded: usize,
+    pub dropped: usize,
+}
+
+impl Report {
+    pub(crate) fn add(
+        &mut self,
+        common: &EntityCommon,
+        entity_type: impl Into<String>,
+        block_path: &[String],
+        outcome: Outcome,
+    ) {
+        self.entities.push(EntityReport {
+            handle: format!("{:X}", common.handle.0),
+            layer: common.layer.clone(),
+            entity_type: entity_type.into(),
+            block_path: block_path.to_vec(),
+            outcome,
+        });
+    }
+
+    #[must_use]
+    pub fn summary(&self) -> Summary {
+        self.entities
+            .iter()
+            .fold(Summary::default(), |mut sum, ent| {
+                match ent.outcome {
+                    Outcome::Converted { .. } => sum.converted += 1,
+                    Outcome::Approximated { .. } => sum.approximated += 1,
+                    Outcome::Expanded { .. } => sum.expanded += 1,
+                    Outcome::Dropped { .. } => sum.dropped += 1,
+                }
+                sum
+            })
+    }
+
+    /// The entities that were left out, with the reason why
+    #[must_use]
+    pub fn warnings(&self) -> Vec<EntityError> {
+        self.entities
+            .iter()
+            .filter_map(|ent| match &ent.outcome {
+                Outcome::Dropped { reason, .. } => Some(EntityError {
+                    handle: ent.handle.clone(),
+                    layer: ent.layer.clone(),
+                    entity_type: ent.entity_type.clone(),
+                    reason: reason.clone(),
+                }),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl Outcome {
+    pub(crate) fn dropped(reason: &ConversionError) -> Self {
+        Self::Dropped {
+            reason: reason.clone(),
+            message: reason.to_string(),
+        }
+    }
+
+    //what the converted entity turned into, going through the groups
+    pub(crate) fn parts(obj: &Objects) -> BTreeMap<String, usize> {
+        let mut parts = BTreeMap::new();
+        for obj in std::iter::once(obj).chain(obj.descendants()) {
+            let name = match obj {
+                Objects::Arc(_) => "arc",
+                Objects::Ellipse(_) => "ellipse",
+                Objects::Polygon(_) => "polygon",
+                Objects::DynamicText(_) => "dynamic_text",
+                Objects::Text(_) => "text",
+                Objects::Line(_) => "line",
+                Objects::Terminal(_) => "terminal",
+                Objects::Group(_) => continue,
+            };
+            *parts.entry(name.to_string()).or_default() += 1;
+        }
+        parts
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for ent in &self.entities {
+            write!(f, "{} {} (layer {}", ent.entity_type, ent.handle, ent.layer)?;
+            if !ent.block_path.is_empty() {
+                write!(f, ", in {}", ent.block_path.join(" > "))?;
+            }
+            write!(f, "): ")?;
+            match &ent.outcome {
+                Outcome::Converted { parts } => write!(f, "converted into {}", Parts(parts))?,
+                Outcome::Approximated { parts, reason } => {
+                    write!(f, "approximated with {}, {reason}", Parts(parts))?;
+                }
+                Outcome::Expanded { block } => write!(f, "expanded from block {block}")?,
+                Outcome::Dropped { message, .. } => write!(f, "dropped, {message}")?,
+            }
+            writeln!(f)?;
+        }
+
+        let sum = self.summary();
+        write!(
+            f,
+            "{} converted, {} approximated, {} expanded, {} dropped",
+            sum.converted, sum.approximated, sum.expanded, sum.dropped
+        )
+    }
+}
+
+struct Parts<'a>(&'a BTreeMap<String, usize>);
+
+impl Display for Parts<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.0.is_empty() {
+            return write!(f, "nothing");
+        }
+        let parts: Vec<String> = self
+            .0
+            .iter()
+            .map(|(name, count)| format!("{count} {name}"))
+            .collect();
+        write!(f, "{}", parts.join(", "))
+    }
+}
@@ -0,0 +1,519 @@
+use super::color::{aci_to_rgb, qet_color_name};
+use super::dimension::DimStyle;
+use super::text::TextMode;
+use crate::ConversionOptions;
+use dxf::entities::EntityCommon;
+use dxf::tables::Layer;
+use dxf::Drawing;
+use hex_color::HexColor;
+use std::fmt::Display;
+use std::str::FromStr;
+
+//the values QET understands for the line-style part of the style attribute
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineStyle {
+    #[default]
+    Normal,
+    Dashed,
+    Dotted,
+    DashDotted,
+}
+
+impl Display for LineStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Normal => "normal",
+                Self::Dashed => "dashed",
+                Self::Dotted => "dotted",
+                Self::DashDotted => "dashdotted",
+            }
+        )
+    }
+}
+
+impl LineStyle {
+    /// The dashes and gaps Qt draws the line style with, in multiples of the line width.
+    /// Empty for a solid line
+    #[must_use]
+    pub fn dashes(self) -> &'static [f64] {
+        match self {
+            Self::Normal => &[],
+            Self::Dashed => &[4.0, 2.0],
+            Self::Dotted => &[1.0, 2.0],
+            Self::DashDotted => &[4.0, 2.0, 1.0, 2.0],
+        }
+    }
+}
+
+impl FromStr for LineStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "normal" => Ok(Self::Normal),
+            "dashed" => Ok(Self::Dashed),
+            "dotted" => Ok(Self::Dotted),
+            "dashdotted" => Ok(Self::DashDotted),
+            _ => Err(format!(
+                "unknown line style '{s}', expected one of normal, dashed, dotted or dashdotted"
+            )),
+        }
+    }
+}
+
+//the values QET understands for the line-weight part of the style attribute,
+//yes "hight" and "eleve" are really what QET calls them
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineWeight {
+    None,
+    #[default]
+    Thin,
+    Normal,
+    Hight,
+    Eleve,
+}
+
+impl Display for LineWeight {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::None => "none",
+                Self::Thin => "thin",
+                Self::Normal => "normal",
+                Self::Hight => "hight",
+                Self::Eleve => "eleve",
+            }
+        )
+    }
+}
+
+impl LineWeight {
+    /// How wide QET draws the line, 1, 2 and 5 with thin being as thin as it gets.
+    /// None when there's no line at all
+    #[must_use]
+    pub fn width(self) -> Option<f64> {
+        match self {
+            Self::None => None,
+            Self::Thin => Some(0.5),
+            Self::Normal => Some(1.0),
+            Self::Hight => Some(2.0),
+            Self::Eleve => Some(5.0),
+        }
+    }
+}
+
+//The smallest DXF lineweight (in mm) that gets mapped onto each of the QET line weights,
+//anything under `thin` ends up as none
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LineWeightBreakpoints {
+    pub thin: f64,
+    pub normal: f64,
+    pub hight: f64,
+    pub eleve: f64,
+}
+
+impl Default for LineWeightBreakpoints {
+    //QET draws thin at half a pixel and then 1, 2 and 5 pixels, with 1mm being 2px
+    //these land the standard lineweights roughly halfway between those
+    fn default() -> Self {
+        Self {
+            thin: 0.0,
+            normal: 0.35,
+            hight: 0.7,
+            eleve: 1.4,
+        }
+    }
+}
+
+impl LineWeightBreakpoints {
+    #[must_use]
+    pub fn line_weight(&self, mm: f64) -> LineWeight {
+        if mm >= self.eleve {
+            LineWeight::Eleve
+        } else if mm >= self.hight {
+            LineWeight::Hight
+        } else if mm >= self.normal {
+            LineWeight::Normal
+        } else if mm >= self.thin {
+            LineWeight::Thin
+        } else {
+            LineWeight::None
+        }
+    }
+}
+
+impl FromStr for LineWeightBreakpoints {
+    type Err = String;
+
+    //as THIN,NORMAL,HIGHT,ELEVE in mm, e.g. 0,0.35,0.7,1.4
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let vals = s
+            .split(',')
+            .map(|val| {
+                val.trim()
+                    .parse::<f64>()
+                    .map_err(|e| format!("invalid lineweight '{val}': {e}"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let [thin, normal, hight, eleve] = vals[..] else {
+            return Err(format!(
+                "expected 4 comma separated values (thin,normal,hight,eleve), got {}",
+                vals.len()
+            ));
+        };
+
+        if !(thin <= normal && normal <= hight && hight <= eleve) {
+            return Err("the lineweight breakpoints need to be in increasing order".into());
+        }
+
+        Ok(Self {
+            thin,
+            normal,
+            hight,
+            eleve,
+        })
+    }
+}
+
+//The style attribute written out on all the geometry primitives
+#[derive(Debug, Clone)]
+pub struct Style {
+    pub line_style: LineStyle,
+    pub line_weight: LineWeight,
+    pub filling: String,
+    pub color: String,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self {
+            line_style: LineStyle::Normal,
+            line_weight: LineWeight::Thin,
+            filling: "none".into(),
+            color: "black".into(),
+        }
+    }
+}
+
+impl Style {
+    pub fn weight(line_weight: LineWeight) -> Self {
+        Self {
+            line_weight,
+            ..Default::default()
+        }
+    }
+}
+
+impl Display for Style {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line-style:{};line-weight:{};filling:{};color:{}",
+            self.line_style, self.line_weight, self.filling, self.color
+        )
+    }
+}
+
+//What an insert hands down to the entities in its block that are set to BYBLOCK,
+//already resolved against the insert's own layer (and whatever block it's in)
+#[derive(Debug, Clone)]
+pub(crate) struct ByBlock {
+    pub layer: String,
+    pub line_type: String,
+    pub line_weight: i16,
+    pub color: HexColor,
+}
+
+impl Default for ByBlock {
+    fn default() -> Self {
+        Self {
+            layer: "0".into(),
+            line_type: "CONTINUOUS".into(),
+            line_weight: LINEWEIGHT_DEFAULT,
+            color: HexColor::BLACK,
+        }
+    }
+}
+
+//The raw lineweight values (group 370) are in 100ths of a mm, except for these
+const LINEWEIGHT_BYBLOCK: i16 = -1;
+const LINEWEIGHT_BYLAYER: i16 = -2;
+const LINEWEIGHT_DEFAULT: i16 = -3;
+
+//What AutoCAD uses for LWDEFAULT out of the box, the dxf crate doesn't read that header variable
+const LINEWEIGHT_DEFAULT_MM: f64 = 0.25;
+
+//Resolves the style related properties of an entity against the tables of the drawing
+pub(crate) struct StyleResolver<'a> {
+    drw: &'a Drawing,
+    options: &'a ConversionOptions,
+}
+
+impl<'a> StyleResolver<'a> {
+    pub fn new(drw: &'a Drawing, options: &'a ConversionOptions) -> Self {
+        Self { drw, options }
+    }
+
+    //Entities on layer 0 inside of a block take on the layer of the insert
+    pub fn layer_name(&self, ent: &EntityCommon, by_block: &ByBlock) -> String {
+        if ent.layer == "0" {
+            by_block.layer.clone()
+        } else {
+            ent.layer.clone()
+        }
+    }
+
+    fn layer(&self, name: &str) -> Option<&'a Layer> {
+        self.drw
+            .layers()
+            .find(|layer| layer.name.eq_ignore_ascii_case(name))
+    }
+
+    //The name of the line type that ends up being used for the entity, after following BYLAYER and BYBLOCK
+    pub fn line_type_name(&self, ent: &EntityCommon, by_block: &ByBlock) -> String {
+        let name = &ent.line_type_name;
+        if name.is_empty() || name.eq_ignore_ascii_case("BYLAYER") {
+            self.layer(&self.layer_name(ent, by_block))
+                .map(|layer| layer.line_type_name.clone())
+                .filter(|name| !name.is_empty())
+                .unwrap_or_else(|| "CONTINUOUS".into())
+        } else if name.eq_ignore_ascii_case("BYBLOCK") {
+            by_block.line_type.clone()
+        } else {
+            name.clone()
+        }
+    }
+
+    //The raw lineweight that ends up being used for the entity, after following BYLAYER and BYBLOCK.
+    //It can still come out as the default lineweight
+    pub fn line_weight_raw(&self, ent: &EntityCommon, by_block: &ByBlock) -> i16 {
+        match ent.lineweight_enum_value {
+            LINEWEIGHT_BYLAYER => self
+                .layer(&self.layer_name(ent, by_block))
+                .map(|layer| layer.line_weight.raw_value())
+                //a layer can't really be BYLAYER or BYBLOCK itself
+                .filter(|lw| *lw >= 0)
+                .unwrap_or(LINEWEIGHT_DEFAULT),
+            LINEWEIGHT_BYBLOCK => by_block.line_weight,
+            lw => lw,
+        }
+    }
+
+    pub fn line_weight(&self, ent: &EntityCommon, by_block: &ByBlock) -> LineWeight {
+        let raw = self.line_weight_raw(ent, by_block);
+        let mm = if raw < 0 {
+            LINEWEIGHT_DEFAULT_MM
+        } else {
+            f64::from(raw) / 100.0
+        };
+        self.options.line_weights.line_weight(mm)
+    }
+
+    //Falls back to the dimension variables in the header when the style isn't in the DIMSTYLE table
+    pub fn dim_style(&self, name: &str) -> DimStyle {
+        let hdr = &self.drw.header;
+        let (scale, text_height, arrow_size, decimal_places, angle_decimal_places) = self
+            .drw
+            .dim_styles()
+            .find(|ds| ds.name.eq_ignore_ascii_case(name))
+            .map_or(
+                (
+                    hdr.dimensioning_scale_factor,
+                    hdr.dimensioning_text_height,
+                    hdr.dimensioning_arrow_size,
+                    hdr.dimension_unit_tolerance_decimal_places,
+                    hdr.angular_dimension_precision,
+                ),
+                |ds| {
+                    (
+                        ds.dimensioning_scale_factor,
+                        ds.dimensioning_text_height,
+                        ds.dimensioning_arrow_size,
+                        ds.dimension_unit_tolerance_decimal_places,
+                        ds.angular_dimension_precision,
+                    )
+                },
+            );
+
+        //a scale of 0 means it's scaled to the layout, which we don't have, so just leave it be
+        let scale = if scale > 0.0 { scale } else { 1.0 };
+        let decimal_places = usize::try_from(decimal_places).unwrap_or(2);
+
+        //DIMADEC goes up to 8 places, -1 means the same as the linear dimensions
+        let angle_decimal_places = match angle_decimal_places {
+            -1 => decimal_places,
+            places @ 0..=8 => usize::try_from(places).unwrap_or_default(),
+            _ => 0,
+        };
+        DimStyle {
+            text_height: text_height * scale,
+            arrow_size: arrow_size * scale,
+            decimal_places,
+            angle_decimal_places,
+        }
+    }
+
+    //The color of the entity after following BYLAYER and BYBLOCK, a true color always wins over the index
+    pub fn color(&self, ent: &EntityCommon, by_block: &ByBlock) -> HexColor {
+        if self.options.monochrome {
+            return HexColor::BLACK;
+        }
+
+        //the dxf crate leaves this as 0 when there isn't a true color, so that's
+        //the one true color we can't tell apart from not having one
+        #[allow(clippy::cast_sign_loss)]
+        let color = if ent.color_24_bit != 0 {
+            HexColor::from_u24(ent.color_24_bit as u32 & 0x00FF_FFFF)
+        } else if ent.color.is_by_layer() {
+            self.layer(&self.layer_name(ent, by_block))
+                .and_then(|layer| layer.color.index())
+                .map_or(HexColor::BLACK, aci_to_rgb)
+        } else if ent.color.is_by_block() {
+            by_block.color
+        } else {
+            ent.color.index().map_or(HexColor::BLACK, aci_to_rgb)
+        };
+
+        //same as with color 7, white would disappear on the white background in QET
+        if color == HexColor::WHITE {
+            HexColor::BLACK
+        } else {
+            color
+        }
+    }
+
+    //The geometry in QET can only use a set of named colors
+    pub fn color_name(&self, ent: &EntityCommon, by_block: &ByBlock) -> &'static str {
+        qet_color_name(self.color(ent, by_block))
+    }
+
+    //Whether the text gets turned into static or dynamic text, a rule for the layer it's on wins over the default
+    pub fn text_mode(&self, ent: &EntityCommon, by_block: &ByBlock) -> TextMode {
+        let layer = self.layer_name(ent, by_block);
+        self.options
+            .text_layers
+            .iter()
+            .find_map(|(name, mode)| name.eq_ignore_ascii_case(&layer).then_some(*mode))
+            .unwrap_or(self.options.text_mode)
+    }
+
+    pub fn line_style(&self, ent: &EntityCommon, by_block: &ByBlock) -> LineStyle {
+        let name = self.line_type_name(ent, by_block);
+
+        //a mapping from the user always wins
+        if let Some(style) = self
+            .options
+            .line_types
+            .iter()
+            .find_map(|(lt, style)| lt.eq_ignore_ascii_case(&name).then_some(*style))
+        {
+            return style;
+        }
+
+        if name.eq_ignore_ascii_case("CONTINUOUS") {
+            return LineStyle::Normal;
+        }
+
+        //Otherwise look at the pattern in the LTYPE table, and only fall back
+        //to guessing from the name if the line type isn't in there
+        self.drw
+            .line_types()
+            .find(|lt| lt.name.eq_ignore_ascii_case(&name))
+            .map_or_else(
+                || Self::line_style_from_name(&name),
+                |lt| Self::line_style_from_pattern(&lt.dash_dot_space_lengths),
+            )
+    }
+
+    //In the pattern a positive length is a dash, a negative one a space, and 0 a dot.
+    //A short dash between long ones (CENTER, PHANTOM) is drawn as a dot too
+    fn line_style_from_pattern(pattern: &[f64]) -> LineStyle {
+        let longest = pattern.iter().copied().fold(0.0, f64::max);
+        let dashes = longest > f64::EPSILON;
+        let dots = pattern.iter().any(|len| len.abs() <= f64::EPSILON);
+        let short_dashes = pattern
+            .iter()
+            .any(|len| *len > f64::EPSILON && *len <= longest / 2.0);
+        let spaces = pattern.iter().any(|len| *len < -f64::EPSILON);
+
+        match (dashes, dots || short_dashes, spaces) {
+            (true, true, _) => LineStyle::DashDotted,
+            (false, true, _) => LineStyle::Dotted,
+            (true, false, true) => LineStyle::Dashed,
+            _ => LineStyle::Normal,
+        }
+    }
+
+    //based off of the names of the line types that ship with AutoCAD (and the ISO ones)
+    fn line_style_from_name(name: &str) -> LineStyle {
+        let name = name.to_ascii_uppercase();
+        if ["DASHDOT", "DASH_DOT", "CENTER", "PHANTOM", "DIVIDE"]
+            .iter()
+            .any(|pat| name.contains(pat))
+        {
+            LineStyle::DashDotted
+        } else if name.contains("DOT") {
+            LineStyle::Dotted
+        } else if name.contains("DASH") || name.contains("HIDDEN") {
+            LineStyle::Dashed
+        } else {
+            LineStyle::Normal
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LineStyle, StyleResolver};
+
+    //the patterns from acad.lin for the line types the name guess knows about
+    #[test]
+    fn pattern_and_name_agree() {
+        let line_types: [(&str, &[f64], LineStyle); 4] = [
+            ("CENTER", &[1.25, -0.25, 0.25, -0.25], LineStyle::DashDotted),
+            (
+                "PHANTOM",
+                &[1.25, -0.25, 0.25, -0.25, 0.25, -0.25],
+                LineStyle::DashDotted,
+            ),
+            ("HIDDEN", &[0.25, -0.125], LineStyle::Dashed),
+            ("DOT", &[0.0, -0.25], LineStyle::Dotted),
+        ];
+        for (name, pattern, expected) in line_types {
+            assert_eq!(
+                StyleResolver::line_style_from_pattern(pattern),
+                expected,
+                "{name}"
+            );
+            assert_eq!(
+                StyleResolver::line_style_from_name(name),
+                expected,
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn dash_dot_pattern() {
+        //DASHDOT: a dash, a space, a dot, a space
+        assert_eq!(
+            StyleResolver::line_style_from_pattern(&[0.5, -0.25, 0.0, -0.25]),
+            LineStyle::DashDotted
+        );
+        //equal dashes are still just dashed
+        assert_eq!(
+            StyleResolver::line_style_from_pattern(&[0.5, -0.25, 0.5, -0.25]),
+            LineStyle::Dashed
+        );
+        assert_eq!(
+            StyleResolver::line_style_from_pattern(&[]),
+            LineStyle::Normal
+        );
+    }
+}
@@ -0,0 +1,140 @@
+use super::color::named_color;
+use super::style::Style;
+use super::{two_dec, Objects};
+use simple_xml_builder::XMLElement;
+use std::collections::BTreeSet;
+
+//Drawing the element as SVG, to see what the conversion came up with without opening QET.
+//Everything is drawn in QET coordinates, which already have y going down like SVG
+
+/// Turns a part of the element into SVG
+pub(crate) trait ToSvg {
+    fn to_svg(&self, fills: &mut PatternFills) -> XMLElement;
+}
+
+/// The pattern fillings (hor, ver, bdiag, fdiag) used in the drawing, by filling and color,
+/// they're drawn with `<pattern>`s that all go in the `<defs>` of the SVG
+#[derive(Debug, Default)]
+pub(crate) struct PatternFills(BTreeSet<(&'static str, String)>);
+
+impl PatternFills {
+    //the reference to the pattern to fill with, None when it's not a pattern filling
+    fn fill(&mut self, filling: &str, color: &str) -> Option<String> {
+        let (name, _) = PATTERNS.iter().find(|(name, _)| *name == filling)?;
+        let id = format!("fill-{name}-{color}");
+        self.0.insert((name, color.to_string()));
+        Some(format!("url(#{id})"))
+    }
+
+    pub fn defs(&self) -> XMLElement {
+        let mut defs = XMLElement::new("defs");
+        for (name, color) in &self.0 {
+            let Some((_, path)) = PATTERNS.iter().find(|(pat, _)| pat == name) else {
+                continue;
+            };
+            let mut pattern = XMLElement::new("pattern");
+            pattern.add_attribute("id", format!("fill-{name}-{color}"));
+            pattern.add_attribute("patternUnits", "userSpaceOnUse");
+            pattern.add_attribute("width", PATTERN_SIZE);
+            pattern.add_attribute("height", PATTERN_SIZE);
+
+            let mut lines = XMLElement::new("path");
+            lines.add_attribute("d", path);
+            lines.add_attribute("stroke", named_color(color).display_rgb());
+            lines.add_attribute("stroke-width", 0.5);
+            pattern.add_child(lines);
+            defs.add_child(pattern);
+        }
+        defs
+    }
+}
+
+//how far apart the lines of the pattern fillings are, about what Qt's brush patterns look like
+pub(crate) const PATTERN_SIZE: f64 = 4.0;
+
+//one tile of each of the patterns, the diagonals are drawn past the corners so they join up
+const PATTERNS: [(&str, &str); 4] = [
+    ("hor", "M0,2 H4"),
+    ("ver", "M2,0 V4"),
+    ("bdiag", "M-1,1 L1,-1 M0,4 L4,0 M3,5 L5,3"),
+    ("fdiag", "M-1,3 L1,5 M0,0 L4,4 M3,-1 L5,1"),
+];
+
+/// Sets the stroke and fill of a shape from its QET style
+pub(crate) fn add_style(elem: &mut XMLElement, style: &Style, fills: &mut PatternFills) {
+    match style.line_weight.width() {
+        Some(width) => {
+            elem.add_attribute("stroke", named_color(&style.color).display_rgb());
+            elem.add_attribute("stroke-width", width);
+
+            let dashes = style.line_style.dashes();
+            if !dashes.is_empty() {
+                let unit = width.max(1.0);
+                let dashes: Vec<String> = dashes
+                    .iter()
+                    .map(|dash| two_dec(dash * unit).to_string())
+                    .collect();
+                elem.add_attribute("stroke-dasharray", dashes.join(","));
+            }
+        }
+        None => elem.add_attribute("stroke", "none"),
+    }
+
+    let fill = match style.filling.as_str() {
+        "none" => "none".to_string(),
+        filling => fills
+            .fill(filling, &style.color)
+            .unwrap_or_else(|| named_color(filling).display_rgb().to_string()),
+    };
+    elem.add_attribute("fill", fill);
+}
+
+/// The lines of a text, each one a `<tspan>` starting at `x`, the first one
+/// on `baseline` and the rest of them `line_height` further down each time
+pub(crate) fn text_lines(
+    elem: &mut XMLElement,
+    text: &str,
+    x: f64,
+    baseline: f64,
+    line_height: f64,
+) {
+    for (i, line) in text.lines().enumerate() {
+        let mut tspan = XMLElement::new("tspan");
+        tspan.add_attribute("x", two_dec(x));
+        #[allow(clippy::cast_precision_loss)]
+        tspan.add_attribute("y", two_dec(baseline + line_height * i as f64));
+        tspan.add_text(line);
+        elem.add_child(tspan);
+    }
+}
+
+impl ToSvg for Objects {
+    fn to_svg(&self, fills: &mut PatternFills) -> XMLElement {
+        match self {
+            Objects::Arc(arc) => arc.to_svg(fills),
+            Objects::Ellipse(ell) => ell.to_svg(fills),
+            Objects::Polygon(poly) => poly.to_svg(fills),
+            Objects::DynamicText(dtext) => dtext.to_svg(fills),
+            Objects::Text(txt) => txt.to_svg(fills),
+            Objects::Line(line) => line.to_svg(fills),
+            Objects::Terminal(term) => term.to_svg(fills),
+            Objects::Group(group) => {
+                let mut g = XMLElement::new("g");
+                for obj in group {
+                    g.add_child(obj.to_svg(fills));
+                }
+                g
+            }
+        }
+    }
+}
+
+/// A cross where the hotspot of the element is, which is the origin
+pub(crate) fn hotspot() -> XMLElement {
+    let mut mark = XMLElement::new("path");
+    mark.add_attribute("d", "M-3,0 H3 M0,-3 V3");
+    mark.add_attribute("stroke", "#FF0000");
+    mark.add_attribute("stroke-width", 0.5);
+    mark.add_attribute("fill", "none");
+    mark
+}
@@ -0,0 +1,276 @@
+use super::color::named_color;
+use super::raster::{polyline, Canvas, Paint};
+use super::style::{LineWeight, Style};
+use super::svg::{PatternFills, ToSvg};
+use super::{two_dec, wildcard_match, BoundingBox, Bounds, Objects, Transform, TransformEntity};
+use simple_xml_builder::XMLElement;
+use std::fmt::Display;
+use uuid::Uuid;
+
+/// Where the connection points of the element are taken from. Nothing in a DXF
+/// is a terminal as such, so it goes by naming conventions in the drawing
+#[derive(Debug, Clone)]
+pub struct TerminalRules {
+    /// Points, and circles no bigger than `max_radius`, on these layers are terminals.
+    /// Names are matched case insensitively
+    pub layers: Vec<String>,
+
+    /// Inserts of the blocks with these names are terminals, `*` matches anything,
+    /// e.g. TERMINAL*. The value of the first attribute of the insert names the terminal
+    pub blocks: Vec<String>,
+
+    /// Attribute definitions with these tags are terminals, named after their default value
+    pub tags: Vec<String>,
+
+    /// The biggest radius (in mm) of a circle on one of the `layers` that's still a terminal
+    pub max_radius: f64,
+}
+
+impl Default for TerminalRules {
+    fn default() -> Self {
+        Self {
+            layers: vec!["TERMINALS".into()],
+            blocks: vec!["TERMINAL*".into()],
+            tags: vec!["TERM".into()],
+            max_radius: 2.0,
+        }
+    }
+}
+
+impl TerminalRules {
+    //the same rules with `max_radius` in the units of the drawing, `scale_factor`
+    //being how many QET units (half a millimetre each) there are to one of them
+    pub(crate) fn in_drawing_units(&self, scale_factor: f64) -> Self {
+        Self {
+            max_radius: self.max_radius * 2.0 / scale_factor,
+            ..self.clone()
+        }
+    }
+
+    #[must_use]
+    pub fn is_terminal_layer(&self, layer: &str) -> bool {
+        self.layers
+            .iter()
+            .any(|name| name.eq_ignore_ascii_case(layer))
+    }
+
+    #[must_use]
+    pub fn is_terminal_block(&self, block: &str) -> bool {
+        self.blocks
+            .iter()
+            .any(|pattern| wildcard_match(pattern, block))
+    }
+
+    #[must_use]
+    pub fn is_terminal_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|name| name.eq_ignore_ascii_case(tag))
+    }
+}
+
+/// Which way the wire leaves the terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TermOrient {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Display for TermOrient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::North => "n",
+                Self::East => "e",
+                Self::South => "s",
+                Self::West => "w",
+            }
+        )
+    }
+}
+
+#[derive(Debug)]
+enum TerminalType {
+    Generic,
+    /*Inner,
+    Outer,*/
+}
+
+impl Display for TerminalType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Generic => "Generic",
+                /*Self::Inner => "Inner",
+                Self::Outer => "Outer",*/
+            }
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct Terminal {
+    x: f64,
+    y: f64,
+    pub(crate) uuid: Uuid,
+    name: String,
+    orientation: TermOrient,
+    r#type: TerminalType,
+}
+
+//how far the terminal sticks out of the element in the previews, about what the element editor shows
+const TERMINAL_LENGTH: f64 = 4.0;
+
+impl Terminal {
+    /// A terminal at the point, which is in drawing coordinates. It points north
+    /// until the element is put together and [`Terminal::orient`] works out the real direction
+    pub fn new(x: f64, y: f64, name: impl Into<String>) -> Self {
+        Self {
+            x,
+            y: -y,
+            uuid: Uuid::new_v4(),
+            name: name.into(),
+            orientation: TermOrient::North,
+            r#type: TerminalType::Generic,
+        }
+    }
+
+    /// Points the terminal out of the edge of the element it's closest to
+    pub fn orient(&mut self, bbox: &BoundingBox) {
+        let edges = [
+            (self.y - bbox.top, TermOrient::North),
+            (bbox.right - self.x, TermOrient::East),
+            (bbox.bottom - self.y, TermOrient::South),
+            (self.x - bbox.left, TermOrient::West),
+        ];
+        if let Some((_, orientation)) = edges.into_iter().min_by(|(a, _), (b, _)| a.total_cmp(b)) {
+            self.orientation = orientation;
+        }
+    }
+
+    //the end of the terminal the wire connects to
+    fn end(&self) -> (f64, f64) {
+        match self.orientation {
+            TermOrient::North => (self.x, self.y - TERMINAL_LENGTH),
+            TermOrient::East => (self.x + TERMINAL_LENGTH, self.y),
+            TermOrient::South => (self.x, self.y + TERMINAL_LENGTH),
+            TermOrient::West => (self.x - TERMINAL_LENGTH, self.y),
+        }
+    }
+}
+
+impl From<&Terminal> for XMLElement {
+    fn from(term: &Terminal) -> Self {
+        let mut term_xml = XMLElement::new("terminal");
+        term_xml.add_attribute("x", two_dec(term.x));
+        term_xml.add_attribute("y", two_dec(term.y));
+        term_xml.add_attribute("uuid", format!("{{{}}}", term.uuid));
+        term_xml.add_attribute("name", &term.name);
+        term_xml.add_attribute("orientation", term.orientation);
+        term_xml.add_attribute("type", &term.r#type);
+        term_xml
+    }
+}
+
+impl TransformEntity for Terminal {
+    fn transform(mut self, tf: &Transform) -> Objects {
+        (self.x, self.y) = tf.apply_qet(self.x, self.y);
+        Objects::Terminal(self)
+    }
+}
+
+impl Bounds for Terminal {
+    fn bounding_box(&self) -> BoundingBox {
+        BoundingBox {
+            left: self.x,
+            top: self.y,
+            right: self.x,
+            bottom: self.y,
+        }
+    }
+}
+
+//drawn in blue like the element editor does, so they stand out from the drawing
+impl ToSvg for Terminal {
+    fn to_svg(&self, _fills: &mut PatternFills) -> XMLElement {
+        let (end_x, end_y) = self.end();
+        let mut term_svg = XMLElement::new("path");
+        term_svg.add_attribute(
+            "d",
+            format!(
+                "M{},{} L{},{}",
+                two_dec(self.x),
+                two_dec(self.y),
+                two_dec(end_x),
+                two_dec(end_y)
+            ),
+        );
+        term_svg.add_attribute("stroke", named_color("blue").display_rgb());
+        term_svg.add_attribute("stroke-width", 1);
+        term_svg.add_attribute("fill", "none");
+        term_svg
+    }
+}
+
+impl Paint for Terminal {
+    fn paint(&self, canvas: &mut Canvas) {
+        let style = Style {
+            color: "blue".into(),
+            line_weight: LineWeight::Normal,
+            ..Style::default()
+        };
+        if let Some(path) = polyline([(self.x, self.y), self.end()], false) {
+            canvas.stroke(&path, &style);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TermOrient, Terminal, TerminalRules};
+    use crate::qelmt::BoundingBox;
+
+    #[test]
+    fn default_rules() {
+        let rules = TerminalRules::default();
+        assert!(rules.is_terminal_layer("terminals"));
+        assert!(!rules.is_terminal_layer("TERMINALS2"));
+        assert!(rules.is_terminal_block("TERMINAL"));
+        assert!(rules.is_terminal_block("TERMINAL_IN"));
+        assert!(!rules.is_terminal_block("MY_TERMINAL"));
+        assert!(rules.is_terminal_tag("term"));
+        assert!(!rules.is_terminal_tag("TERMINAL"));
+    }
+
+    #[test]
+    fn max_radius_in_drawing_units() {
+        //2mm is 4 QET units, which is 2 units of a drawing scaled up by 2
+        let rules = TerminalRules::default().in_drawing_units(2.0);
+        assert!((rules.max_radius - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn orient_points_out_of_the_closest_edge() {
+        //10 wide and 20 tall, with the top at -20 since QET's y goes down
+        let bbox = BoundingBox {
+            left: 0.0,
+            top: -20.0,
+            right: 10.0,
+            bottom: 0.0,
+        };
+        let orientation = |x, y| {
+            let mut term = Terminal::new(x, y, "");
+            term.orient(&bbox);
+            term.orientation
+        };
+
+        assert_eq!(orientation(5.0, 19.0), TermOrient::North);
+        assert_eq!(orientation(9.0, 10.0), TermOrient::East);
+        assert_eq!(orientation(5.0, 1.0), TermOrient::South);
+        assert_eq!(orientation(1.0, 10.0), TermOrient::West);
+    }
+}
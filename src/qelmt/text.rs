@@ -1,7 +1,38 @@
-use super::{two_dec, FontInfo, ScaleEntity};
+use super::dynamictext::{extract_mtext_font, normalize_mtext};
+use super::metrics::TextSize;
+use super::raster::{Canvas, Paint};
+use super::svg::{text_lines, PatternFills, ToSvg};
+use super::{two_dec, BoundingBox, Bounds, FontInfo, Objects, Transform, TransformEntity};
 use dxf::entities;
+use dxf::enums::AttachmentPoint;
 use hex_color::HexColor;
 use simple_xml_builder::XMLElement;
+use std::str::FromStr;
+
+/// What TEXT and MTEXT entities get turned into
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TextMode {
+    /// Plain static text, it's part of the drawing of the element and can't be edited once placed
+    Static,
+
+    /// Dynamic text, which can be moved around and edited in the diagram
+    #[default]
+    Dynamic,
+}
+
+impl FromStr for TextMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "static" => Ok(Self::Static),
+            "dynamic" => Ok(Self::Dynamic),
+            _ => Err(format!(
+                "unknown text mode '{s}', expected either static or dynamic"
+            )),
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct Text {
@@ -11,6 +42,9 @@ pub struct Text {
     pub y: f64,
     font: FontInfo,
     color: HexColor,
+
+    //measure the text with the font instead of guessing from the number of characters
+    pub(crate) font_metrics: bool,
 }
 
 impl From<(&entities::Text, HexColor)> for Text {
@@ -18,19 +52,55 @@ impl From<(&entities::Text, HexColor)> for Text {
         Text {
             x: txt.location.x,
             y: -txt.location.y,
-            rotation: if txt.rotation.abs().round() as i64 % 360 != 0 {
-                txt.rotation - 180.0
-            } else {
-                0.0
-            },
+            //dxf rotates counter clockwise, QET clockwise
+            rotation: -txt.rotation,
             color,
-            font: if &txt.text_style_name == "STANDARD" {
-                FontInfo::default()
-            } else {
-                //txt.text_style_name.clone()
-                FontInfo::default()
+            font: FontInfo {
+                point_size: txt.text_height,
+                ..Default::default()
             },
             value: txt.value.clone(),
+            font_metrics: true,
+        }
+    }
+}
+
+impl From<(&entities::MText, HexColor)> for Text {
+    fn from((mtxt, color): (&entities::MText, HexColor)) -> Self {
+        let mut raw = mtxt.extended_text.join("");
+        raw.push_str(&mtxt.text);
+
+        let mut font = FontInfo {
+            point_size: mtxt.initial_text_height,
+            ..Default::default()
+        };
+        if let Some(family) = extract_mtext_font(&raw) {
+            font.family = family;
+        }
+
+        //A static text sits on the baseline of its first line, the insertion point of an MTEXT
+        //is on the box around it. There isn't a way to line it up horizontally without knowing
+        //how wide the text is, but at least get it on the right line
+        let drop = match mtxt.attachment_point {
+            AttachmentPoint::TopLeft | AttachmentPoint::TopCenter | AttachmentPoint::TopRight => {
+                mtxt.initial_text_height
+            }
+            AttachmentPoint::MiddleLeft
+            | AttachmentPoint::MiddleCenter
+            | AttachmentPoint::MiddleRight => mtxt.initial_text_height / 2.0,
+            _ => 0.0,
+        };
+        let (sin, cos) = mtxt.rotation_angle.to_radians().sin_cos();
+
+        Text {
+            x: mtxt.insertion_point.x + drop * sin,
+            y: -(mtxt.insertion_point.y - drop * cos),
+            //dxf rotates counter clockwise, QET clockwise
+            rotation: -mtxt.rotation_angle,
+            color,
+            font,
+            value: normalize_mtext(&raw),
+            font_metrics: true,
         }
     }
 }
@@ -48,29 +118,68 @@ impl From<&Text> for XMLElement {
     }
 }
 
-impl ScaleEntity for Text {
-    fn scale(&mut self, fact_x: f64, fact_y: f64) {
-        self.x *= fact_x;
-        self.y *= fact_y;
-        //self.font.pixel_size *= fact;
-        self.font.point_size *= fact_x;
-    }
-
-    fn left_bound(&self) -> f64 {
-        self.x
+impl TransformEntity for Text {
+    fn transform(mut self, tf: &Transform) -> Objects {
+        (self.x, self.y) = tf.apply_qet(self.x, self.y);
+        self.rotation = tf.text_rotation_qet(self.rotation);
+        //QET can't stretch text, so a non uniform scale only changes the height
+        self.font.point_size *= tf.scale_y();
+        Objects::Text(self)
     }
+}
 
-    fn top_bound(&self) -> f64 {
-        self.y
+impl Bounds for Text {
+    fn bounding_box(&self) -> BoundingBox {
+        //the position is on the baseline of the first line, with the rest of the lines below it
+        TextSize::measure(&self.value, &self.font, self.font_metrics).placed(
+            self.x,
+            self.y,
+            0.0,
+            -self.font.point_size,
+            self.rotation,
+        )
     }
+}
 
-    fn right_bound(&self) -> f64 {
-        //need to be able to measure text size to get this
-        todo!()
+impl ToSvg for Text {
+    fn to_svg(&self, _fills: &mut PatternFills) -> XMLElement {
+        let mut txt_svg = XMLElement::new("text");
+        txt_svg.add_attribute("font-family", &self.font.family);
+        txt_svg.add_attribute("font-size", two_dec(self.font.point_size));
+        txt_svg.add_attribute("fill", self.color.display_rgb());
+        if self.rotation != 0.0 {
+            txt_svg.add_attribute(
+                "transform",
+                format!(
+                    "rotate({} {} {})",
+                    two_dec(self.rotation),
+                    two_dec(self.x),
+                    two_dec(self.y)
+                ),
+            );
+        }
+        //the position is on the baseline of the first line
+        text_lines(
+            &mut txt_svg,
+            &self.value,
+            self.x,
+            self.y,
+            self.font.point_size * TextSize::LINE_SPACING,
+        );
+        txt_svg
     }
+}
 
-    fn bot_bound(&self) -> f64 {
-        //need to be able to measure text size to get this
-        todo!()
+impl Paint for Text {
+    fn paint(&self, canvas: &mut Canvas) {
+        canvas.text(
+            &self.value,
+            &self.font,
+            (self.x, self.y),
+            0.0,
+            self.rotation,
+            (self.x, self.y),
+            self.color,
+        );
     }
 }
@@ -0,0 +1,265 @@
+use dxf::entities::{Entity, EntityType};
+
+//A 2D affine transform, used to carry the placement of inserted blocks down to the
+//entities inside of them. It's stored in dxf coordinates (y up), the same as the
+//insert values it's built from, the *_qet functions take care of the y flip for
+//the objects that have already been converted into QET coordinates.
+//
+//The layout is the same as an SVG matrix(a, b, c, d, e, f):
+//  x' = a*x + c*y + e
+//  y' = b*x + d*y + f
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Transform {
+    a: f64,
+    b: f64,
+    c: f64,
+    d: f64,
+    e: f64,
+    f: f64,
+}
+
+//the angle between the points when an arc or ellipse has to be tessellated
+const TESSELLATE_STEP_DEG: f64 = 5.0;
+
+//used when checking if the transform keeps things axis aligned or uniform, and if an
+//arc or ellipse is circular. The values come out of sin/cos so they're rarely ever exactly 0
+pub(crate) const EPSILON: f64 = 1e-9;
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Transform {
+    #[must_use]
+    pub fn identity() -> Self {
+        Transform {
+            a: 1.0,
+            b: 0.0,
+            c: 0.0,
+            d: 1.0,
+            e: 0.0,
+            f: 0.0,
+        }
+    }
+
+    #[must_use]
+    pub fn translation(x: f64, y: f64) -> Self {
+        Transform {
+            e: x,
+            f: y,
+            ..Self::identity()
+        }
+    }
+
+    /// Counter clockwise rotation about the origin, in degrees
+    #[must_use]
+    pub fn rotation(degrees: f64) -> Self {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        Transform {
+            a: cos,
+            b: sin,
+            c: -sin,
+            d: cos,
+            ..Self::identity()
+        }
+    }
+
+    /// Negative factors mirror about the matching axis
+    #[must_use]
+    pub fn scaling(fact_x: f64, fact_y: f64) -> Self {
+        Transform {
+            a: fact_x,
+            d: fact_y,
+            ..Self::identity()
+        }
+    }
+
+    /// The transform placing the contents of a block for an insert:
+    /// translate the base point to the origin, scale, rotate and then move to the insert location
+    #[must_use]
+    pub fn insert(ins: &dxf::entities::Insert, base_point: &dxf::Point) -> Self {
+        Self::translation(-base_point.x, -base_point.y)
+            .then(&Self::scaling(ins.x_scale_factor, ins.y_scale_factor))
+            .then(&Self::rotation(ins.rotation))
+            .then(&Self::translation(ins.location.x, ins.location.y))
+    }
+
+    /// Projects the object coordinate system (OCS) of a planar entity onto the world XY plane,
+    /// using the Arbitrary Axis Algorithm from the dxf spec to work out the OCS axes from the
+    /// extrusion direction. `elevation` is the z of the entity's plane in the OCS
+    #[must_use]
+    pub fn ocs(normal: &dxf::Vector, elevation: f64) -> Self {
+        let len = (normal.x.powi(2) + normal.y.powi(2) + normal.z.powi(2)).sqrt();
+        if len < EPSILON {
+            return Self::identity();
+        }
+        let n = (normal.x / len, normal.y / len, normal.z / len);
+        let cross = |a: (f64, f64, f64), b: (f64, f64, f64)| {
+            (
+                a.1 * b.2 - a.2 * b.1,
+                a.2 * b.0 - a.0 * b.2,
+                a.0 * b.1 - a.1 * b.0,
+            )
+        };
+
+        //the spec says to use world y unless the normal is too close to the z axis
+        let ax = if n.0.abs() < 1.0 / 64.0 && n.1.abs() < 1.0 / 64.0 {
+            cross((0.0, 1.0, 0.0), n)
+        } else {
+            cross((0.0, 0.0, 1.0), n)
+        };
+        let ax_len = (ax.0.powi(2) + ax.1.powi(2) + ax.2.powi(2)).sqrt();
+        let ax = (ax.0 / ax_len, ax.1 / ax_len, ax.2 / ax_len);
+        let ay = cross(n, ax);
+
+        //dropping the world z of the result is what projects it onto the drawing
+        Transform {
+            a: ax.0,
+            b: ax.1,
+            c: ay.0,
+            d: ay.1,
+            e: n.0 * elevation,
+            f: n.1 * elevation,
+        }
+    }
+
+    /// The OCS of the entities whose coordinates are given in it rather than in world coordinates
+    #[must_use]
+    pub fn entity_ocs(ent: &Entity) -> Option<Self> {
+        let (normal, elevation) = match &ent.specific {
+            EntityType::Arc(arc) => (&arc.normal, arc.center.z),
+            EntityType::Circle(circle) => (&circle.normal, circle.center.z),
+            //the elevation of a LwPolyline (group 38) ends up with the common properties
+            EntityType::LwPolyline(poly) => (&poly.extrusion_direction, ent.common.elevation),
+            EntityType::Polyline(poly) if !poly.is_3d_polyline() => (&poly.normal, poly.location.z),
+            EntityType::Text(text) => (&text.normal, text.location.z),
+            EntityType::AttributeDefinition(attdef) => (&attdef.normal, attdef.location.z),
+            EntityType::Insert(ins) => (&ins.extrusion_direction, ins.location.z),
+            EntityType::Solid(solid) => (&solid.extrusion_direction, solid.first_corner.z),
+            _ => return None,
+        };
+        Some(Self::ocs(normal, elevation))
+    }
+
+    /// Returns a transform that applies `self` first and then `next`
+    #[must_use]
+    pub fn then(&self, next: &Transform) -> Self {
+        Transform {
+            a: next.a * self.a + next.c * self.b,
+            b: next.b * self.a + next.d * self.b,
+            c: next.a * self.c + next.c * self.d,
+            d: next.b * self.c + next.d * self.d,
+            e: next.a * self.e + next.c * self.f + next.e,
+            f: next.b * self.e + next.d * self.f + next.f,
+        }
+    }
+
+    #[must_use]
+    pub fn apply(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            self.a * x + self.c * y + self.e,
+            self.b * x + self.d * y + self.f,
+        )
+    }
+
+    /// Only the linear part of the transform, for directions and extents
+    #[must_use]
+    pub fn apply_vector(&self, x: f64, y: f64) -> (f64, f64) {
+        (self.a * x + self.c * y, self.b * x + self.d * y)
+    }
+
+    /// Same as `apply` but for a point that is already in QET coordinates (y down)
+    #[must_use]
+    pub fn apply_qet(&self, x: f64, y: f64) -> (f64, f64) {
+        let (x, y) = self.apply(x, -y);
+        (x, -y)
+    }
+
+    #[must_use]
+    pub fn determinant(&self) -> f64 {
+        self.a * self.d - self.b * self.c
+    }
+
+    #[must_use]
+    pub fn is_mirrored(&self) -> bool {
+        self.determinant() < 0.0
+    }
+
+    /// Counter clockwise angle in degrees the x axis ends up at
+    #[must_use]
+    pub fn rotation_deg(&self) -> f64 {
+        self.b.atan2(self.a).to_degrees()
+    }
+
+    /// How much a length along the x axis is stretched
+    #[must_use]
+    pub fn scale_x(&self) -> f64 {
+        self.a.hypot(self.b)
+    }
+
+    /// How much a length along the y axis is stretched
+    #[must_use]
+    pub fn scale_y(&self) -> f64 {
+        self.c.hypot(self.d)
+    }
+
+    /// True when the x and y axes still end up parallel to the x and y axes (in either order)
+    /// so axis aligned shapes like the QET ellipse and arc stay axis aligned
+    #[must_use]
+    pub fn keeps_axes(&self) -> bool {
+        (self.b.abs() < EPSILON && self.c.abs() < EPSILON)
+            || (self.a.abs() < EPSILON && self.d.abs() < EPSILON)
+    }
+
+    /// True when the transform is only made up of translation, rotation, uniform scaling
+    /// and mirroring, so circles stay circles
+    #[must_use]
+    pub fn is_uniform(&self) -> bool {
+        (self.scale_x() - self.scale_y()).abs() < EPSILON * self.scale_x().max(1.0)
+            && (self.a * self.c + self.b * self.d).abs() < EPSILON * self.scale_x().max(1.0)
+    }
+
+    /// The half width and half height of an axis aligned ellipse with the given radii
+    /// after the transform. Exact when `keeps_axes`, or for a circle and a uniform transform,
+    /// otherwise it's the bounding box of the transformed ellipse
+    #[must_use]
+    pub fn radii(&self, rx: f64, ry: f64) -> (f64, f64) {
+        (
+            (self.a * rx).hypot(self.c * ry),
+            (self.b * rx).hypot(self.d * ry),
+        )
+    }
+
+    /// The (cos, sin) pairs for the points along an arc starting at `start` and sweeping
+    /// counter clockwise by `sweep` degrees, including both ends
+    #[must_use]
+    pub fn tessellate(start: f64, sweep: f64) -> Vec<(f64, f64)> {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let steps = (sweep.abs() / TESSELLATE_STEP_DEG).ceil().max(1.0) as usize;
+        (0..=steps)
+            .map(|i| {
+                #[allow(clippy::cast_precision_loss)]
+                let (sin, cos) = (start + sweep * i as f64 / steps as f64)
+                    .to_radians()
+                    .sin_cos();
+                (cos, sin)
+            })
+            .collect()
+    }
+
+    /// Maps a QET text rotation (clockwise, in degrees) through the transform.
+    /// QET can't mirror text, so for a mirrored transform this keeps the text readable
+    /// along the mirrored baseline, which is how CAD programs show it with `MIRRTEXT` off
+    #[must_use]
+    pub fn text_rotation_qet(&self, rotation: f64) -> f64 {
+        let (sin, cos) = (-rotation).to_radians().sin_cos();
+        let (dir_x, dir_y) = self.apply_vector(cos, sin);
+        let mut angle = dir_y.atan2(dir_x).to_degrees();
+        if self.is_mirrored() && dir_x < -EPSILON {
+            angle += 180.0;
+        }
+        -angle
+    }
+}